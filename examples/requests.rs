@@ -64,9 +64,9 @@ pub fn optional_query(q: Result<Query<UserQuery>>) -> Result<JSON<UserQuery>> {
 
 /// tela supports parsing the request body in a parameter.
 /// The type of the body can be string, which retains the body as a raw string,
-/// or as a Deserialize object. This can be a serde Deserialize struct, and it will use serde_json
-/// by default, or it can be any serde_plain supported object. This means things like u32 or Enums
-/// can also be parsed from the body.
+/// or as a Deserialize object. The body is parsed based on its Content-Type: serde_json for json,
+/// serde_qs for url encoded forms, and serde_plain for plain text. This means things like u32 or
+/// Enums can also be parsed from the body. Use `Bytes` or `Vec<u8>` to get the raw body.
 ///
 /// Body is very similar to Query. It can be marked as optional. To allow for missing or invalid
 /// parsing of the body to not result in an error response. See `optional_body` endpoint to see
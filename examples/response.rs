@@ -21,11 +21,11 @@ pub fn redirect() -> Redirect<303> {
 /// header in the response.
 #[get("/error")]
 pub fn server_error() -> Result<String> {
-    // Can return a result of Ok(T) or Err((code, message))
+    // Can return a result of Ok(T) or Err(tela::Error)
     // response! shortcuts and wraps what is inside making the syntax simpler
     response!(StatusCode::InternalServerError, "Custom user error")
     // == response!(500, "Custom user error")
-    // == Err((500, "Custom user error"))
+    // == Err(Error::new(500, "Custom user error"))
 }
 
 /// tela has built in json support using serde_json.
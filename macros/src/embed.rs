@@ -0,0 +1,99 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use proc_macro::TokenStream;
+use proc_macro_error::abort;
+use quote::quote;
+use syn::LitStr;
+
+/// Recursively collect all files in a directory
+fn collect(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// FNV-1a hash of the files contents. Used as a stable etag between builds.
+fn hash(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in data.iter() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+pub fn embed_assets(path: LitStr) -> TokenStream {
+    let manifest = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".to_string());
+    let root = Path::new(&manifest).join(path.value());
+
+    if !root.is_dir() {
+        abort!(path, format!("{:?} is not a directory", root.display()));
+    }
+
+    let mut paths = Vec::new();
+    if let Err(err) = collect(&root, &mut paths) {
+        abort!(
+            path,
+            format!("Failed to read {:?}: {}", root.display(), err)
+        );
+    }
+    paths.sort();
+
+    let files = paths.iter().map(|file| {
+        let data = match fs::read(file) {
+            Ok(data) => data,
+            Err(err) => abort!(
+                path,
+                format!("Failed to read {:?}: {}", file.display(), err)
+            ),
+        };
+
+        let relative = file
+            .strip_prefix(&root)
+            .unwrap()
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<String>>()
+            .join("/");
+        let absolute = file.display().to_string();
+        let etag = format!("\"{:x}-{:x}\"", data.len(), hash(&data));
+        let mime = match file
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| mime_guess::from_ext(ext).first())
+        {
+            Some(mime) => {
+                let mime = mime.to_string();
+                quote!(Some(#mime))
+            }
+            None => quote!(None),
+        };
+
+        quote! {
+            ::tela::assets::EmbeddedFile {
+                path: #relative,
+                data: include_bytes!(#absolute),
+                mime: #mime,
+                etag: #etag,
+            }
+        }
+    });
+
+    let root = root.display().to_string();
+    quote! {
+        ::tela::assets::Embedded {
+            root: #root,
+            files: &[#(#files,)*],
+        }
+    }
+    .into()
+}
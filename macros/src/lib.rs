@@ -1,14 +1,17 @@
 extern crate proc_macro;
 mod docs;
+mod embed;
 mod helpers;
+mod multipart;
 mod request;
+mod validate;
 
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use proc_macro_error::proc_macro_error;
 
 use quote::quote;
-use syn::{parse_macro_input, ItemFn};
+use syn::{parse_macro_input, DeriveInput, ItemFn, LitStr};
 
 use request::{request_catch, request_endpoint, CatchArgs, RequestArgs};
 
@@ -69,6 +72,54 @@ pub fn main(_: TokenStream, function: TokenStream) -> TokenStream {
     .into()
 }
 
+/// Embed a directory, relative to the crate root, into the binary
+///
+/// # Example
+/// ```ignore
+/// static ASSETS: Embedded = embed_assets!("assets/");
+/// ```
+#[proc_macro_error]
+#[proc_macro]
+pub fn embed_assets(input: TokenStream) -> TokenStream {
+    embed::embed_assets(parse_macro_input!(input as LitStr))
+}
+
+/// Derive `FromMultipart` to build the struct from a `multipart/form-data` body
+///
+/// # Example
+/// ```ignore
+/// #[derive(FromMultipart)]
+/// #[multipart(limit = "20MB")]
+/// struct Profile {
+///     name: String,
+///     #[multipart(limit = "5MB")]
+///     avatar: TempFile,
+/// }
+/// ```
+#[proc_macro_error]
+#[proc_macro_derive(FromMultipart, attributes(multipart))]
+pub fn from_multipart(input: TokenStream) -> TokenStream {
+    multipart::derive_from_multipart(parse_macro_input!(input as DeriveInput))
+}
+
+/// Derive `Validate` from `#[validate(...)]` rules on the fields
+///
+/// # Example
+/// ```ignore
+/// #[derive(Validate)]
+/// struct Signup {
+///     #[validate(length(min = 3, max = 20))]
+///     username: String,
+///     #[validate(email)]
+///     email: String,
+/// }
+/// ```
+#[proc_macro_error]
+#[proc_macro_derive(Validate, attributes(validate))]
+pub fn validate(input: TokenStream) -> TokenStream {
+    validate::derive_validate(parse_macro_input!(input as DeriveInput))
+}
+
 #[proc_macro]
 pub fn html(input: TokenStream) -> TokenStream {
     let input: TokenStream2 = input.into();
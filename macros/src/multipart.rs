@@ -0,0 +1,136 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span};
+use proc_macro_error::abort;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Fields};
+
+use super::{
+    helpers::{get_path_generic, get_path_name},
+    request::parse_size,
+};
+
+/// Get the `limit` from `#[multipart(limit = "...")]` attributes
+fn limit(attrs: &[Attribute]) -> Option<usize> {
+    let mut limit = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("multipart")) {
+        let result = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("limit") {
+                limit = Some(parse_size(meta.value()?)?);
+                Ok(())
+            } else {
+                Err(meta.error("Unkown argument; expected `limit`"))
+            }
+        });
+        if let Err(err) = result {
+            abort!(attr, err.to_string());
+        }
+    }
+    limit
+}
+
+pub fn derive_from_multipart(input: DeriveInput) -> TokenStream {
+    let name = input.ident.clone();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect::<Vec<_>>(),
+            _ => abort!(
+                input,
+                "FromMultipart can only be derived for structs with named fields"
+            ),
+        },
+        _ => abort!(input, "FromMultipart can only be derived for structs"),
+    };
+
+    let total = match limit(&input.attrs) {
+        Some(limit) => quote!(let __multipart = __multipart.limit(#limit);),
+        None => quote!(),
+    };
+
+    let mut storage = Vec::new();
+    let mut arms = Vec::new();
+    let mut values = Vec::new();
+
+    for field in fields.iter() {
+        let ident = field.ident.clone().unwrap();
+        let key = ident.to_string().trim_start_matches("r#").to_string();
+        let var = Ident::new(&format!("__value_{}", key), Span::call_site());
+
+        let (kind, inner) = match get_path_name(&field.ty).as_str() {
+            "Option" => ("Option", get_path_generic(&field.ty)),
+            "Vec" => ("Vec", get_path_generic(&field.ty)),
+            _ => ("", field.ty.clone()),
+        };
+
+        let read = match get_path_name(&inner).as_str() {
+            "TempFile" => quote!(__field.temp_file()?),
+            _ => quote! {
+                __field.text()?.parse::<#inner>().map_err(|_| {
+                    ::tela::Error::new(422, format!("Invalid value for multipart field {}", #key))
+                })?
+            },
+        };
+        let field_limit = match limit(&field.attrs) {
+            Some(limit) => quote!(let __field = __field.limit(#limit);),
+            None => quote!(),
+        };
+
+        match kind {
+            "Vec" => {
+                storage.push(quote!(let mut #var: Vec<#inner> = Vec::new();));
+                arms.push(quote!(Some(#key) => {
+                    #field_limit
+                    #var.push(#read);
+                }));
+                values.push(quote!(#ident: #var));
+            }
+            "Option" => {
+                storage.push(quote!(let mut #var: Option<#inner> = None;));
+                arms.push(quote!(Some(#key) => {
+                    #field_limit
+                    #var = Some(#read);
+                }));
+                values.push(quote!(#ident: #var));
+            }
+            _ => {
+                storage.push(quote!(let mut #var: Option<#inner> = None;));
+                arms.push(quote!(Some(#key) => {
+                    #field_limit
+                    #var = Some(#read);
+                }));
+                values.push(quote! {
+                    #ident: #var.ok_or_else(|| {
+                        ::tela::Error::new(422, format!("Missing multipart field {}", #key))
+                    })?
+                });
+            }
+        }
+    }
+
+    quote! {
+        impl #impl_generics ::tela::request::FromMultipart for #name #ty_generics #where_clause {
+            fn from_multipart(
+                __multipart: ::tela::request::Multipart
+            ) -> ::tela::response::Result<Self> {
+                #total
+                let mut __multipart = __multipart;
+                #(#storage)*
+
+                while let Some(__field) = __multipart.next_field()? {
+                    let __key = __field.name().map(|name| name.to_string());
+                    match __key.as_deref() {
+                        #(#arms)*
+                        // Unknown fields are skipped
+                        _ => {}
+                    }
+                }
+
+                Ok(#name {
+                    #(#values,)*
+                })
+            }
+        }
+    }
+    .into()
+}
@@ -1,5 +1,5 @@
 use proc_macro::TokenStream;
-use proc_macro2::{Span, TokenStream as TokenStream2};
+use proc_macro2::TokenStream as TokenStream2;
 use proc_macro_error::abort;
 use quote::quote;
 use syn::{
@@ -15,6 +15,33 @@ use super::{
 pub struct RequestArgs {
     pub path: LitStr,
     pub methods: Vec<String>,
+    pub limit: Option<usize>,
+}
+
+/// Parse a size like `512`, `"10KB"`, or `"50MB"` into bytes
+pub fn parse_size(input: syn::parse::ParseStream) -> syn::Result<usize> {
+    if input.peek(LitInt) {
+        return input.parse::<LitInt>()?.base10_parse::<usize>();
+    }
+
+    let size: LitStr = input.parse()?;
+    let value = size.value().trim().to_ascii_uppercase();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier: usize = match unit.trim() {
+        "" | "B" => 1,
+        "KB" | "KIB" => 1 << 10,
+        "MB" | "MIB" => 1 << 20,
+        "GB" | "GIB" => 1 << 30,
+        _ => abort!(size, "Unknown size unit; expected B, KB, MB, or GB"),
+    };
+
+    match number.parse::<usize>() {
+        Ok(number) => Ok(number * multiplier),
+        _ => abort!(size, "Expected a size like \"50MB\""),
+    }
 }
 
 impl Parse for RequestArgs {
@@ -26,58 +53,130 @@ impl Parse for RequestArgs {
         let _: Result<Token![,]> = input.parse();
 
         let mut methods = Vec::new();
-        if input.peek(Ident) {
+        let mut limit = None;
+        while input.peek(Ident) {
             let next: Ident = input.parse()?;
-            if next != "methods" {
-                abort!(input.span(), "Unkown argument");
+            let _: Token![=] = input.parse()?;
+
+            match next.to_string().as_str() {
+                "methods" => {
+                    let list;
+                    bracketed!(list in input);
+
+                    let req_methods = Punctuated::<Ident, Token![,]>::parse_terminated(&list)?;
+                    methods = req_methods
+                        .into_iter()
+                        .map(|m| m.to_string().to_uppercase())
+                        .collect()
+                }
+                "limit" => limit = Some(parse_size(input)?),
+                _ => abort!(next, "Unkown argument"),
             }
 
-            let _: Token![=] = input.parse()?;
-            let list;
-            bracketed!(list in input);
-
-            let req_methods = Punctuated::<Ident, Token![,]>::parse_terminated(&list)?;
-            methods = req_methods
-                .into_iter()
-                .map(|m| m.to_string().to_uppercase())
-                .collect()
+            let _: Result<Token![,]> = input.parse();
         }
 
-        Ok(RequestArgs { path, methods })
+        Ok(RequestArgs {
+            path,
+            methods,
+            limit,
+        })
     }
 }
 
 pub struct CatchArgs {
-    pub code: syn::LitInt,
+    /// Inclusive ranges of the caught codes. Empty catches every code.
+    pub codes: Vec<(u16, u16)>,
+    pub prefix: Option<LitStr>,
+}
+
+/// Parse a single code, a class like `4xx`, or a range like `500..=599`
+fn parse_codes(input: syn::parse::ParseStream) -> syn::Result<(u16, u16)> {
+    let start: LitInt = input.parse()?;
+    if start.suffix() == "xx" {
+        let class = start.base10_parse::<u16>()?;
+        if !(1..=5).contains(&class) {
+            abort!(start, "Expected a status class from 1xx to 5xx");
+        }
+        return Ok((class * 100, class * 100 + 99));
+    }
+
+    let code = start.base10_parse::<u16>()?;
+    if input.peek(Token![..=]) {
+        let _: Token![..=] = input.parse()?;
+        let end: LitInt = input.parse()?;
+        let end = end.base10_parse::<u16>()?;
+        if end < code {
+            abort!(start, "The end of the range must be greater than the start");
+        }
+        Ok((code, end))
+    } else if input.peek(Token![..]) {
+        let _: Token![..] = input.parse()?;
+        let end: LitInt = input.parse()?;
+        let end = end.base10_parse::<u16>()?;
+        if end <= code {
+            abort!(start, "The end of the range must be greater than the start");
+        }
+        Ok((code, end - 1))
+    } else {
+        Ok((code, code))
+    }
 }
 
 impl Parse for CatchArgs {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        if input.is_empty() {
-            return Ok(CatchArgs {
-                code: LitInt::new("0", Span::call_site()),
-            });
+        let mut codes = Vec::new();
+        let mut all = false;
+        let mut prefix = None;
+
+        while !input.is_empty() {
+            if input.peek(LitInt) {
+                codes.push(parse_codes(input)?);
+            } else {
+                let next: Ident = input.parse()?;
+                match next.to_string().as_str() {
+                    "all" => all = true,
+                    "prefix" => {
+                        let _: Token![=] = input.parse()?;
+                        prefix = Some(input.parse::<LitStr>()?);
+                    }
+                    _ => abort!(
+                        next,
+                        "Expected a status code, class like `4xx`, range like `500..=599`, `all`, or `prefix = \"...\"`"
+                    ),
+                }
+            }
+
+            if !input.is_empty() {
+                let _: Token![,] = input.parse()?;
+            }
+        }
+
+        // `0` has always meant every code
+        if all || codes.contains(&(0, 0)) {
+            codes.clear();
         }
 
-        let code = match input.parse::<LitInt>() {
-            Ok(c) => c,
-            _ => match input.parse::<Ident>() {
-                Ok(c) if c.to_string().as_str() == "all" => LitInt::new("0", Span::call_site()),
-                _ => abort!(
-                    input.span(),
-                    "Expected single u16 or `all` identifier argument"
-                ),
-            },
-        };
-
-        Ok(CatchArgs { code })
+        Ok(CatchArgs { codes, prefix })
     }
 }
 
-fn parse_props(path: String, function: &ItemFn) -> TokenStream2 {
-    let mut props: Vec<String> = Vec::new();
-    let captures: Vec<String> = path
-        .split("/")
+/// Extractors that read the request body as it arrives
+const STREAMED: [&str; 3] = ["BodyStream", "Multipart", "MultipartForm"];
+
+/// Extractors that never read the request body
+const BODYLESS: [&str; 7] = [
+    "Query",
+    "Accept",
+    "LastEventId",
+    "WebSocketUpgrade",
+    "Uri",
+    "Method",
+    "HeaderMap",
+];
+
+fn parse_captures(path: &str) -> Vec<String> {
+    path.split("/")
         .filter_map(|p| {
             if p.starts_with(":...") {
                 Some(p.strip_prefix(":...").unwrap().to_string())
@@ -87,12 +186,59 @@ fn parse_props(path: String, function: &ItemFn) -> TokenStream2 {
                 None
             }
         })
-        .collect();
+        .collect()
+}
+
+/// Decide whether the body should be ignored, buffered, or streamed based on the extractors
+fn body_usage(path: &str, function: &ItemFn) -> TokenStream2 {
+    let captures = parse_captures(path);
+    let mut streamed = None;
+    let mut buffered = false;
+
+    for arg in function.sig.inputs.iter() {
+        if let FnArg::Typed(PatType { ty, pat, .. }) = arg {
+            if let Pat::Ident(PatIdent { ident, .. }) = &(**pat) {
+                if captures.contains(&ident.to_string()) {
+                    continue;
+                }
+            }
+
+            // Look through wrappers to the extractor itself
+            let mut inner = (**ty).clone();
+            while matches!(
+                get_path_name(&inner).as_str(),
+                "Option" | "Result" | "Valid"
+            ) {
+                inner = get_path_generic(&inner);
+            }
+            let name = get_path_name(&inner);
+            if STREAMED.contains(&name.as_str()) {
+                streamed = Some(ty);
+            } else if !BODYLESS.contains(&name.as_str()) {
+                buffered = true;
+            }
+        }
+    }
+
+    match (streamed, buffered) {
+        (Some(ty), true) => abort!(
+            ty,
+            "Streamed bodies can not be combined with other extractors that read the body"
+        ),
+        (Some(_), false) => quote!(::tela::request::BodyUsage::Streamed),
+        (None, true) => quote!(::tela::request::BodyUsage::Buffered),
+        (None, false) => quote!(::tela::request::BodyUsage::Ignored),
+    }
+}
+
+fn parse_props(path: String, function: &ItemFn) -> TokenStream2 {
+    let mut props: Vec<String> = Vec::new();
+    let captures = parse_captures(&path);
 
     let error = |a: FnArg| {
         abort!(
             a,
-            format!("Invalid endpoint argument: expected Query or Body types; or a uri capture")
+            format!("Invalid endpoint argument: expected a request extractor like Query or Body; or a uri capture")
         )
     };
 
@@ -131,7 +277,7 @@ fn parse_props(path: String, function: &ItemFn) -> TokenStream2 {
                                         .get(\"{}\")
                                         .unwrap_or(&String::new())
                                         .parse::<{}>()
-                                        .map_err(|e| (500, e.to_string()))",
+                                        .map_err(|e| ::tela::Error::new(500, e.to_string()))",
                                     ident, ty
                                 ))
                             } else {
@@ -141,11 +287,31 @@ fn parse_props(path: String, function: &ItemFn) -> TokenStream2 {
                             props.push(data)
                         }
                     }
+                    // Invalid values are answered with the validation errors
+                    "Valid" => props.push(
+                        "match __data.to_param() {
+                            Ok(result) => match ::tela::validation::Valid::check(result) {
+                                Ok(valid) => valid,
+                                Err(errors) => return ::tela::response::ToResponse::to_response(
+                                    errors,
+                                    __method,
+                                    __uri,
+                                    __headers,
+                                    String::new(),
+                                ),
+                            },
+                            Err(e) => return Err(e)
+                        }"
+                        .to_string(),
+                    ),
                     _ => {
                         if let Pat::Ident(PatIdent { ident, .. }) = &(**pat) {
                             if captures.contains(&ident.to_string()) {
                                 props.push(format!(
-                                    "__captures.get(\"{}\").unwrap().parse::<{}>().unwrap()",
+                                    "match __captures.get(\"{0}\").map(|value| value.parse::<{1}>()) {{
+                                        Some(Ok(value)) => value,
+                                        _ => return Err(::tela::Error::new(400, \"Invalid uri capture {0}\")),
+                                    }}",
                                     ident,
                                     quote!(#ty)
                                 ))
@@ -164,6 +330,35 @@ fn parse_props(path: String, function: &ItemFn) -> TokenStream2 {
     props.join(",").parse::<TokenStream2>().unwrap()
 }
 
+/// Arguments of a catch handler. `code`, `message`, and `reason` are given by name and any
+/// other argument is a request extractor.
+fn parse_catch_props(function: &ItemFn) -> TokenStream2 {
+    let props = function.sig.inputs.iter().map(|arg| match arg {
+        FnArg::Typed(PatType { pat, .. }) => {
+            let name = match &(**pat) {
+                Pat::Ident(PatIdent { ident, .. }) => ident.to_string(),
+                _ => String::new(),
+            };
+            match name.as_str() {
+                "code" => quote!(__code),
+                "message" => quote!(__message.clone()),
+                "reason" => quote!(__reason.clone()),
+                _ => quote! {
+                    match __data.to_param() {
+                        Ok(result) => result,
+                        Err(e) => return Err(e)
+                    }
+                },
+            }
+        }
+        _ => abort!(
+            arg,
+            "Invalid catch argument: expected `code`, `message`, `reason`, or a request extractor"
+        ),
+    });
+    quote!(#(#props),*)
+}
+
 pub fn request_endpoint(args: RequestArgs, mut function: ItemFn) -> TokenStream {
     let uri = args.path.value();
     let path = args.path;
@@ -188,6 +383,11 @@ pub fn request_endpoint(args: RequestArgs, mut function: ItemFn) -> TokenStream
     .unwrap();
 
     let props = parse_props(path.value().to_string(), &function);
+    let body_usage = body_usage(&path.value(), &function);
+    let body_limit = match args.limit {
+        Some(limit) => quote!(Some(#limit)),
+        None => quote!(None),
+    };
     let name = function.sig.ident.clone();
     let vis = function.vis.clone();
     function.sig.ident = Ident::new("__call", function.sig.ident.span());
@@ -209,20 +409,40 @@ pub fn request_endpoint(args: RequestArgs, mut function: ItemFn) -> TokenStream
                 String::from(#path)
             }
 
+            #[inline]
+            fn body_usage(&self) -> ::tela::request::BodyUsage {
+                #body_usage
+            }
+
+            #[inline]
+            fn body_limit(&self) -> Option<usize> {
+                #body_limit
+            }
+
             fn execute(
                 &self,
                 __method: &::tela::bump::hyper::Method,
                 __uri: &mut ::tela::bump::hyper::Uri,
+                __headers: &::tela::bump::hyper::HeaderMap,
                 __body: &mut Vec<u8>,
-            ) -> ::tela::response::Result<::tela::bump::hyper::Response<::tela::bump::http_body_util::Full<::tela::bump::bytes::Bytes>>> {
+                __extensions: &mut ::tela::bump::hyper::http::Extensions,
+            ) -> ::tela::response::Result<::tela::response::Response> {
                 #[inline]
                 #function
 
                 let __captures = ::tela::uri::props(&__uri.path().to_string(), &self.path());
-                let mut __data = ::tela::request::RequestData(__uri.clone(), __method.clone(), __body.clone());
+                let mut __data = ::tela::request::RequestData(
+                    __uri.clone(),
+                    __method.clone(),
+                    __body.clone(),
+                    __headers.clone(),
+                    std::mem::take(__extensions),
+                    None,
+                );
                 __call(#props).to_response(
                     __method,
                     __uri,
+                    __headers,
                     std::str::from_utf8(__body.as_slice()).unwrap_or("").to_string()
                 )
             }
@@ -234,18 +454,38 @@ pub fn request_endpoint(args: RequestArgs, mut function: ItemFn) -> TokenStream
 pub fn request_catch(args: CatchArgs, mut function: ItemFn) -> TokenStream {
     let name = function.sig.ident.clone();
     let vis = function.vis.clone();
-    let code = args.code;
+    let caught = match args.codes.is_empty() {
+        true => "any".to_string(),
+        false => args
+            .codes
+            .iter()
+            .map(|(start, end)| match start == end {
+                true => start.to_string(),
+                false => format!("{}-{}", start, end),
+            })
+            .collect::<Vec<String>>()
+            .join(", "),
+    };
+    let scope = match &args.prefix {
+        Some(prefix) => format!(" under `{}`", prefix.value()),
+        None => String::new(),
+    };
     let docs = format!(
-        "#[doc=\"Catches {} errors and handles them\n\n{}\"]",
-        match code.to_string().as_str() {
-            "0" => "any",
-            val => val,
-        },
+        "#[doc=\"Catches {} errors{} and handles them\n\n{}\"]",
+        caught,
+        scope,
         compile_docs(&mut function)
     )
     .parse::<TokenStream2>()
     .unwrap();
 
+    let codes = args.codes.iter().map(|(start, end)| quote!(#start..=#end));
+    let prefix = match args.prefix {
+        Some(prefix) => quote!(Some(String::from(#prefix))),
+        None => quote!(None),
+    };
+
+    let props = parse_catch_props(&function);
     function.sig.ident = proc_macro2::Ident::new("__callback", function.sig.ident.span());
     function.vis = syn::Visibility::Inherited;
 
@@ -259,18 +499,43 @@ pub fn request_catch(args: CatchArgs, mut function: ItemFn) -> TokenStream {
         impl ::tela::request::Catch for #name {
             fn execute(
                 &self,
-                code: u16,
-                message: String,
-                reason: String
-            ) -> ::tela::response::Result<::tela::bump::hyper::Response<::tela::bump::http_body_util::Full<bytes::Bytes>>> {
+                __method: &::tela::bump::hyper::Method,
+                __uri: &::tela::bump::hyper::Uri,
+                __headers: &::tela::bump::hyper::HeaderMap,
+                __body: &[u8],
+                __error: ::tela::Error,
+            ) -> ::tela::response::Result<::tela::response::Response> {
                 #function
 
-                __callback(code.clone(), message, reason.clone()).to_error_response(code, reason)
+                let __code = __error.get_status();
+                #[allow(unused_variables)]
+                let __message = ::tela::StatusCode::from(__code).message();
+                let __reason = __error.reason();
+                #[allow(unused_mut, unused_variables)]
+                let mut __data = ::tela::request::RequestData(
+                    __uri.clone(),
+                    __method.clone(),
+                    __body.to_vec(),
+                    __headers.clone(),
+                    ::tela::bump::hyper::http::Extensions::new(),
+                    Some(__error),
+                );
+                ::tela::response::ToErrorResponse::to_error_response_with(
+                    __callback(#props),
+                    __code,
+                    __reason,
+                    __headers,
+                )
+            }
+
+            #[inline]
+            fn codes(&self) -> Vec<::std::ops::RangeInclusive<u16>> {
+                vec![#(#codes),*]
             }
 
             #[inline]
-            fn code(&self) -> u16 {
-                #code
+            fn prefix(&self) -> Option<String> {
+                #prefix
             }
         }
     }
@@ -0,0 +1,159 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::abort;
+use quote::quote;
+use syn::{meta::ParseNestedMeta, Data, DeriveInput, Expr, Fields, LitStr, Path};
+
+use super::helpers::get_path_name;
+
+/// Parse `min = ..., max = ...` bounds of the `length` and `range` rules
+fn bounds(meta: &ParseNestedMeta) -> syn::Result<(Option<Expr>, Option<Expr>)> {
+    let mut min = None;
+    let mut max = None;
+    meta.parse_nested_meta(|bound| {
+        if bound.path.is_ident("min") {
+            min = Some(bound.value()?.parse::<Expr>()?);
+        } else if bound.path.is_ident("max") {
+            max = Some(bound.value()?.parse::<Expr>()?);
+        } else {
+            return Err(bound.error("Unkown bound; expected `min` or `max`"));
+        }
+        Ok(())
+    })?;
+    Ok((min, max))
+}
+
+/// Generate the checks for every rule on a field
+fn rules(field: &str, attrs: &[syn::Attribute]) -> Vec<TokenStream2> {
+    let mut checks = Vec::new();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("validate")) {
+        let result = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("length") {
+                let (min, max) = bounds(&meta)?;
+                if let Some(min) = min {
+                    checks.push(quote! {
+                        if ::tela::validation::Length::length(__value) < (#min) as usize {
+                            __errors.add(#field, "length", format!("must have a length of at least {}", #min));
+                        }
+                    });
+                }
+                if let Some(max) = max {
+                    checks.push(quote! {
+                        if ::tela::validation::Length::length(__value) > (#max) as usize {
+                            __errors.add(#field, "length", format!("must have a length of at most {}", #max));
+                        }
+                    });
+                }
+            } else if meta.path.is_ident("range") {
+                let (min, max) = bounds(&meta)?;
+                if let Some(min) = min {
+                    checks.push(quote! {
+                        if *__value < #min {
+                            __errors.add(#field, "range", format!("must be at least {}", #min));
+                        }
+                    });
+                }
+                if let Some(max) = max {
+                    checks.push(quote! {
+                        if *__value > #max {
+                            __errors.add(#field, "range", format!("must be at most {}", #max));
+                        }
+                    });
+                }
+            } else if meta.path.is_ident("email") {
+                checks.push(quote! {
+                    if !::tela::validation::is_email(__value) {
+                        __errors.add(#field, "email", "must be a valid email address");
+                    }
+                });
+            } else if meta.path.is_ident("regex") {
+                let pattern: LitStr = meta.value()?.parse()?;
+                // Check the pattern while compiling so a typo isn't a panic at runtime
+                if let Err(err) = regex::Regex::new(&pattern.value()) {
+                    abort!(pattern, format!("Invalid regex: {}", err));
+                }
+                checks.push(quote! {
+                    {
+                        static __PATTERN: ::std::sync::OnceLock<::tela::bump::regex::Regex> =
+                            ::std::sync::OnceLock::new();
+                        let __pattern = __PATTERN.get_or_init(|| {
+                            ::tela::bump::regex::Regex::new(#pattern).expect("invalid validation regex")
+                        });
+                        if !__pattern.is_match(__value) {
+                            __errors.add(#field, "regex", format!("must match the pattern {}", #pattern));
+                        }
+                    }
+                });
+            } else if meta.path.is_ident("custom") {
+                let function: LitStr = meta.value()?.parse()?;
+                let function: Path = function.parse()?;
+                checks.push(quote! {
+                    if let Err(__message) = #function(__value) {
+                        __errors.add(#field, "custom", __message);
+                    }
+                });
+            } else {
+                return Err(meta.error(
+                    "Unkown rule; expected `length`, `range`, `email`, `regex`, or `custom`",
+                ));
+            }
+            Ok(())
+        });
+
+        if let Err(err) = result {
+            abort!(attr, err.to_string());
+        }
+    }
+    checks
+}
+
+pub fn derive_validate(input: DeriveInput) -> TokenStream {
+    let name = input.ident.clone();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect::<Vec<_>>(),
+            _ => abort!(
+                input,
+                "Validate can only be derived for structs with named fields"
+            ),
+        },
+        _ => abort!(input, "Validate can only be derived for structs"),
+    };
+
+    let validations = fields.iter().filter_map(|field| {
+        let ident = field.ident.clone().unwrap();
+        let key = ident.to_string().trim_start_matches("r#").to_string();
+        let checks = rules(&key, &field.attrs);
+        if checks.is_empty() {
+            return None;
+        }
+
+        Some(match get_path_name(&field.ty).as_str() {
+            "Option" => quote! {
+                if let Some(__value) = &self.#ident {
+                    #(#checks)*
+                }
+            },
+            _ => quote! {
+                {
+                    let __value = &self.#ident;
+                    #(#checks)*
+                }
+            },
+        })
+    });
+
+    quote! {
+        impl #impl_generics ::tela::validation::Validate for #name #ty_generics #where_clause {
+            fn validate(&self) -> ::std::result::Result<(), ::tela::validation::ValidationErrors> {
+                #[allow(unused_mut)]
+                let mut __errors = ::tela::validation::ValidationErrors::new();
+                #(#validations)*
+                __errors.into_result()
+            }
+        }
+    }
+    .into()
+}
@@ -0,0 +1,92 @@
+use super::source::Entry;
+
+/// A file baked into the binary with `embed_assets!`
+#[derive(Debug)]
+pub struct EmbeddedFile {
+    /// Path relative to the embedded directory using `/` as the separator
+    pub path: &'static str,
+    pub data: &'static [u8],
+    pub mime: Option<&'static str>,
+    pub etag: &'static str,
+}
+
+/// A directory baked into the binary with `embed_assets!`
+///
+/// # Example
+/// ```no_run
+/// use tela::{assets::{embed_assets, Embedded}, Server};
+///
+/// static ASSETS: Embedded = embed_assets!("assets/");
+///
+/// #[tela::main]
+/// async fn main() {
+///     Server::new()
+///         .assets(&ASSETS)
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Embedded {
+    /// Absolute path of the directory when it was embedded
+    pub root: &'static str,
+    pub files: &'static [EmbeddedFile],
+}
+
+impl Embedded {
+    /// Get the file at the path relative to the embedded directory
+    pub fn get(&self, path: &str) -> Option<&EmbeddedFile> {
+        let path = path.trim_matches('/');
+        self.files.iter().find(|file| file.path == path)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &EmbeddedFile> {
+        self.files.iter()
+    }
+
+    /// Check if any embedded file is inside of the directory at the path
+    pub fn is_dir(&self, path: &str) -> bool {
+        let path = path.trim_matches('/');
+        path.is_empty()
+            || self
+                .files
+                .iter()
+                .any(|file| file.path.starts_with(&format!("{}/", path)))
+    }
+
+    pub(crate) fn entries(&self, path: &str) -> Vec<Entry> {
+        let path = path.trim_matches('/');
+        let prefix = match path.is_empty() {
+            true => String::new(),
+            false => format!("{}/", path),
+        };
+
+        let mut entries: Vec<Entry> = Vec::new();
+        for file in self.files.iter() {
+            let rest = match file.path.strip_prefix(&prefix) {
+                Some(rest) => rest,
+                None => continue,
+            };
+
+            match rest.split_once('/') {
+                Some((directory, _)) => {
+                    if !entries.iter().any(|e| e.directory && e.name == directory) {
+                        entries.push(Entry {
+                            name: directory.to_string(),
+                            directory: true,
+                            size: 0,
+                            modified: None,
+                        })
+                    }
+                }
+                None => entries.push(Entry {
+                    name: rest.to_string(),
+                    directory: false,
+                    size: file.data.len() as u64,
+                    modified: None,
+                }),
+            }
+        }
+        entries
+    }
+}
@@ -0,0 +1,472 @@
+mod embed;
+mod source;
+
+pub use embed::{Embedded, EmbeddedFile};
+pub use source::{Entry, Source};
+pub use tela_macros::embed_assets;
+
+use hyper::{header::HeaderValue, HeaderMap, Method, Uri};
+
+use crate::{
+    encoding::{preferred, Encoding},
+    response::{empty, full, ranged_response, Response},
+};
+
+/// How a directory without an index file is presented
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Listing {
+    /// Directories without an index file fall through to the router
+    Off,
+    /// Render an html page linking to each entry in the directory
+    Html,
+    /// Respond with a json array describing each entry in the directory
+    Json,
+}
+
+/// Result of looking up a request path in an asset mount
+pub(crate) enum Lookup {
+    /// The mount produced a response
+    Found(Response),
+    /// The path looks like a file in this mount, but it doesn't exist
+    Missing,
+    /// The mount doesn't handle the path
+    Pass,
+}
+
+/// A directory of static files served at a uri prefix.
+///
+/// Files are served for any path with an extension before the router is checked. Directories
+/// that aren't matched by the router are served through their index files, or optionally a
+/// listing of their entries. When a fallback file is set, `GET` requests that accept
+/// `text/html` and are not matched by the router are answered with that file which allows
+/// client side routed apps (SPA) to be served.
+///
+/// Precompressed siblings of a file, `app.js.br`, `app.js.zst`, and `app.js.gz`, are served in
+/// place of the file when the client accepts that encoding.
+///
+/// Assets are read from disk by default. Directories embedded with `embed_assets!` can be served
+/// instead for single binary deploys.
+///
+/// # Example
+/// ```no_run
+/// use tela::{assets::{Assets, Listing}, Server};
+///
+/// #[tela::main]
+/// async fn main() {
+///     Server::new()
+///         .assets("assets/")
+///         .assets(Assets::new("docs/").prefix("/docs").listing(Listing::Html))
+///         .assets(Assets::new("app/dist/").prefix("/app").fallback("index.html"))
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Assets {
+    source: Source,
+    prefix: String,
+    index: Vec<String>,
+    listing: Listing,
+    fallback: Option<String>,
+}
+
+impl Assets {
+    /// Serve files from the `root` directory, or embedded directory, at the uri root
+    pub fn new<T: Into<Source>>(root: T) -> Self {
+        Assets {
+            source: root.into(),
+            prefix: String::new(),
+            index: vec!["index.html".to_string()],
+            listing: Listing::Off,
+            fallback: None,
+        }
+    }
+
+    /// Uri prefix the assets are mounted at; ex: `/static`
+    pub fn prefix<T: Into<String>>(mut self, prefix: T) -> Self {
+        self.prefix = normalize_prefix(prefix.into());
+        self
+    }
+
+    /// Files, in order of priority, that are served when a directory is requested
+    pub fn index<T: Into<String>, const SIZE: usize>(mut self, files: [T; SIZE]) -> Self {
+        self.index = files.into_iter().map(|f| f.into()).collect();
+        self
+    }
+
+    /// How directories without an index file are presented
+    pub fn listing(mut self, listing: Listing) -> Self {
+        self.listing = listing;
+        self
+    }
+
+    /// File, relative to the asset root, served for unmatched `GET` requests that accept html
+    pub fn fallback<T: Into<String>>(mut self, file: T) -> Self {
+        self.fallback = Some(file.into());
+        self
+    }
+
+    pub(crate) fn get_prefix(&self) -> &String {
+        &self.prefix
+    }
+
+    /// Get the path relative to this mount if the uri path is inside of it
+    fn relative<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let rest = path.strip_prefix(self.prefix.as_str())?;
+        if !rest.is_empty() && !rest.starts_with('/') {
+            return None;
+        }
+        Some(rest.trim_start_matches('/'))
+    }
+
+    /// Resolve a uri path to a path relative to the source root. Segments are percent decoded
+    /// and paths trying to escape the root are ignored.
+    fn resolve(&self, relative: &str) -> Option<String> {
+        let mut path = Vec::new();
+        for segment in relative.split('/').filter(|s| !s.is_empty()) {
+            let segment = decode(segment)?;
+            if segment == ".." || segment == "." || segment.contains(['/', '\\']) {
+                return None;
+            }
+            path.push(segment);
+        }
+        Some(path.join("/"))
+    }
+
+    /// Look up a file, a path with an extension, in this mount
+    pub(crate) fn file(&self, uri: &Uri, headers: &HeaderMap) -> Lookup {
+        let path = match self.relative(uri.path()).and_then(|r| self.resolve(r)) {
+            Some(path) => path,
+            None => return Lookup::Pass,
+        };
+
+        let has_extension = path
+            .rsplit('/')
+            .next()
+            .map(|name| name.contains('.'))
+            .unwrap_or(false);
+        if !has_extension || self.source.is_dir(&path) {
+            return Lookup::Pass;
+        }
+
+        match serve_file(&self.source, &path, headers) {
+            Some(response) => Lookup::Found(response),
+            None => Lookup::Missing,
+        }
+    }
+
+    /// Serve a directory through its index files or a listing of its entries. Requests
+    /// for directories without a trailing slash are redirected so relative links resolve.
+    pub(crate) fn directory(&self, uri: &Uri, headers: &HeaderMap) -> Option<Response> {
+        let path = self.resolve(self.relative(uri.path())?)?;
+        if !self.source.is_dir(&path) {
+            return None;
+        }
+
+        let index = self
+            .index
+            .iter()
+            .map(|index| join(&path, index))
+            .find(|index| self.source.is_file(index));
+        if index.is_none() && self.listing == Listing::Off {
+            return None;
+        }
+
+        if !uri.path().ends_with('/') {
+            let location = match uri.query() {
+                Some(query) => format!("{}/?{}", uri.path(), query),
+                None => format!("{}/", uri.path()),
+            };
+            return Some(
+                hyper::Response::builder()
+                    .status(301)
+                    .header("Location", location)
+                    .body(empty())
+                    .unwrap(),
+            );
+        }
+
+        match index {
+            Some(index) => serve_file(&self.source, &index, headers),
+            None => listing_response(&self.source, &path, uri.path(), self.listing),
+        }
+    }
+
+    /// Serve the fallback file if the request is a `GET` request inside of this mount that
+    /// accepts html.
+    pub(crate) fn fallback_for(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+    ) -> Option<Response> {
+        let fallback = self.fallback.as_ref()?;
+        if method != Method::GET || self.relative(uri.path()).is_none() {
+            return None;
+        }
+
+        let accepts_html = headers
+            .get("Accept")
+            .and_then(|accept| accept.to_str().ok())
+            .map(|accept| accept.contains("text/html"))
+            .unwrap_or(false);
+        if !accepts_html {
+            return None;
+        }
+
+        serve_file(&self.source, &self.resolve(fallback)?, headers)
+    }
+}
+
+impl From<&str> for Assets {
+    fn from(value: &str) -> Self {
+        Assets::new(value)
+    }
+}
+
+impl From<String> for Assets {
+    fn from(value: String) -> Self {
+        Assets::new(value)
+    }
+}
+
+impl From<&'static Embedded> for Assets {
+    fn from(value: &'static Embedded) -> Self {
+        Assets::new(value)
+    }
+}
+
+fn join(directory: &str, file: &str) -> String {
+    match directory.is_empty() {
+        true => file.to_string(),
+        false => format!("{}/{}", directory, file),
+    }
+}
+
+fn normalize_prefix(prefix: String) -> String {
+    let prefix = prefix.replace('\\', "/");
+    let prefix = prefix.trim_matches('/');
+    if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", prefix)
+    }
+}
+
+/// Precompressed siblings checked for each file; ex: `app.js.br`
+const PRECOMPRESSED: [Encoding; 3] = [Encoding::Brotli, Encoding::Zstd, Encoding::Gzip];
+
+/// Respond with the file at the given path honoring any range headers.
+///
+/// If precompressed siblings of the file exist, the one best matching the request's
+/// `Accept-Encoding` is served instead. The content type is always that of the original file.
+fn serve_file(source: &Source, path: &str, headers: &HeaderMap) -> Option<Response> {
+    if !source.is_file(path) {
+        return None;
+    }
+
+    let sidecars = PRECOMPRESSED
+        .iter()
+        .filter_map(|encoding| {
+            let sidecar = format!("{}.{}", path, encoding.extension()?);
+            source.is_file(&sidecar).then_some((*encoding, sidecar))
+        })
+        .collect::<Vec<(Encoding, String)>>();
+
+    let encoding = preferred(
+        headers,
+        &sidecars.iter().map(|(e, _)| *e).collect::<Vec<Encoding>>(),
+    );
+    let (file, encoding) = match sidecars.iter().find(|(e, _)| Some(*e) == encoding) {
+        Some((encoding, sidecar)) => (sidecar.as_str(), Some(*encoding)),
+        None => (path, None),
+    };
+
+    let (data, validators) = source.read(file)?;
+    let mut response = ranged_response(data, source.mime(path), &validators, headers);
+
+    if let Some(encoding) = encoding {
+        response.headers_mut().insert(
+            "Content-Encoding",
+            HeaderValue::from_static(encoding.name()),
+        );
+    }
+    if !sidecars.is_empty() {
+        response
+            .headers_mut()
+            .insert("Vary", HeaderValue::from_static("Accept-Encoding"));
+    }
+
+    Some(response)
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Percent encode a file name so it can be used as a path segment in a link
+fn encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Decode a percent encoded path segment. Invalid escapes and utf-8 are rejected.
+fn decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn listing_response(
+    source: &Source,
+    path: &str,
+    uri_path: &str,
+    listing: Listing,
+) -> Option<Response> {
+    let entries = source.entries(path)?;
+
+    match listing {
+        Listing::Json => Some(
+            hyper::Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .body(full(serde_json::to_string(&entries).ok()?))
+                .unwrap(),
+        ),
+        _ => {
+            let title = escape(uri_path);
+            let parent = match uri_path {
+                "/" => "",
+                _ => "<li><a href=\"../\">../</a></li>",
+            };
+            let items = entries
+                .iter()
+                .map(|entry| {
+                    let (href, name) = match entry.directory {
+                        true => (
+                            format!("{}/", encode(&entry.name)),
+                            format!("{}/", escape(&entry.name)),
+                        ),
+                        false => (encode(&entry.name), escape(&entry.name)),
+                    };
+                    format!(
+                        "<li><a href=\"{}\">{}</a>{}</li>",
+                        href,
+                        name,
+                        match entry.directory {
+                            true => String::new(),
+                            false => format!(" <small>({} bytes)</small>", entry.size),
+                        }
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("");
+
+            Some(
+                hyper::Response::builder()
+                    .status(200)
+                    .header("Content-Type", "text/html")
+                    .body(full(html_to_string_macro::html! {
+                        <!DOCTYPE html>
+                        <html lang="en">
+                        <head>
+                            <meta charset="UTF-8"/>
+                            <title>"Index of "{title.clone()}</title>
+                        </head>
+                        <body>
+                            <h1>"Index of "{title}</h1>
+                            <ul>
+                                {parent}
+                                {items}
+                            </ul>
+                        </body>
+                        </html>
+                    }))
+                    .unwrap(),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http_body_util::BodyExt;
+
+    use super::{decode, encode, listing_response, Assets, Listing, Source};
+
+    #[test]
+    fn names_are_percent_encoded() {
+        assert_eq!(encode("index.html"), "index.html");
+        assert_eq!(encode("a b#c.txt"), "a%20b%23c.txt");
+        assert_eq!(encode("50%?.md"), "50%25%3F.md");
+        assert_eq!(encode("é"), "%C3%A9");
+    }
+
+    #[test]
+    fn segments_are_percent_decoded() {
+        assert_eq!(decode("a%20b%23c.txt").as_deref(), Some("a b#c.txt"));
+        assert_eq!(decode("%C3%A9").as_deref(), Some("é"));
+        assert_eq!(decode("50%"), None);
+        assert_eq!(decode("%zz"), None);
+        assert_eq!(decode("%FF"), None);
+    }
+
+    #[test]
+    fn resolve_decodes_and_rejects_escapes() {
+        let assets = Assets::new("assets/");
+        assert_eq!(
+            assets.resolve("docs/a%20b%23c.txt").as_deref(),
+            Some("docs/a b#c.txt")
+        );
+        assert_eq!(assets.resolve("%2E%2E/secret"), None);
+        assert_eq!(assets.resolve("a%2Fb"), None);
+        assert_eq!(assets.resolve("a%5Cb"), None);
+    }
+
+    #[test]
+    fn listing_links_are_encoded_and_names_escaped() {
+        let root = std::env::temp_dir().join(format!("tela-listing-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("sub dir")).unwrap();
+        std::fs::write(root.join("a b#c.txt"), "").unwrap();
+        std::fs::write(root.join("<b>.txt"), "").unwrap();
+
+        let source = Source::Disk(root.to_string_lossy().to_string());
+        let response = listing_response(&source, "", "/", Listing::Html).unwrap();
+        let body = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(response.into_body().collect())
+            .unwrap()
+            .to_bytes();
+        let body = String::from_utf8_lossy(&body);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(body.contains(r#"<a href="a%20b%23c.txt">a b#c.txt</a>"#));
+        assert!(body.contains(r#"<a href="%3Cb%3E.txt">&lt;b&gt;.txt</a>"#));
+        assert!(body.contains(r#"<a href="sub%20dir/">sub dir/</a>"#));
+    }
+}
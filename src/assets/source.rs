@@ -0,0 +1,145 @@
+use std::{fs, path::PathBuf, time::UNIX_EPOCH};
+
+use bytes::Bytes;
+
+use crate::response::Validators;
+
+use super::embed::Embedded;
+
+/// Where assets and templates are read from
+///
+/// Paths given to a source are relative to its root and use `/` as the separator.
+#[derive(Debug, Clone)]
+pub enum Source {
+    /// Directory on disk relative to the working directory
+    Disk(String),
+    /// Directory baked into the binary with `embed_assets!`
+    Embedded(&'static Embedded),
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct Entry {
+    pub name: String,
+    pub directory: bool,
+    pub size: u64,
+    pub modified: Option<u64>,
+}
+
+impl Source {
+    fn disk_path(root: &str, path: &str) -> PathBuf {
+        let mut full = PathBuf::from(root);
+        full.extend(path.split('/').filter(|s| !s.is_empty()));
+        full
+    }
+
+    /// Check if a file exists at the path
+    pub fn is_file(&self, path: &str) -> bool {
+        match self {
+            Source::Disk(root) => Source::disk_path(root, path).is_file(),
+            Source::Embedded(embedded) => embedded.get(path).is_some(),
+        }
+    }
+
+    /// Check if a directory exists at the path
+    pub fn is_dir(&self, path: &str) -> bool {
+        match self {
+            Source::Disk(root) => Source::disk_path(root, path).is_dir(),
+            Source::Embedded(embedded) => embedded.is_dir(path),
+        }
+    }
+
+    /// Read the file at the path along with validators for conditional requests
+    pub fn read(&self, path: &str) -> Option<(Bytes, Validators)> {
+        match self {
+            Source::Disk(root) => {
+                let path = Source::disk_path(root, path);
+                let data = fs::read(&path).ok()?;
+                let validators = match fs::metadata(&path) {
+                    Ok(metadata) => Validators::from_metadata(&metadata),
+                    _ => Validators::default(),
+                };
+                Some((Bytes::from(data), validators))
+            }
+            Source::Embedded(embedded) => {
+                let file = embedded.get(path)?;
+                Some((
+                    Bytes::from_static(file.data),
+                    Validators {
+                        etag: Some(file.etag.to_string()),
+                        last_modified: None,
+                    },
+                ))
+            }
+        }
+    }
+
+    /// Mime type of the file at the path
+    pub fn mime(&self, path: &str) -> Option<String> {
+        match self {
+            Source::Embedded(embedded) => match embedded.get(path) {
+                Some(file) => file.mime.map(|mime| mime.to_string()),
+                None => None,
+            },
+            _ => path
+                .rsplit_once('.')
+                .and_then(|(_, ext)| mime_guess::from_ext(ext).first())
+                .map(|mime| mime.to_string()),
+        }
+    }
+
+    /// Entries of the directory at the path. Directories are first then all entries are sorted by
+    /// name.
+    pub fn entries(&self, path: &str) -> Option<Vec<Entry>> {
+        let mut entries = match self {
+            Source::Disk(root) => fs::read_dir(Source::disk_path(root, path))
+                .ok()?
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let metadata = entry.metadata().ok()?;
+                    Some(Entry {
+                        name: entry.file_name().to_string_lossy().to_string(),
+                        directory: metadata.is_dir(),
+                        size: metadata.len(),
+                        modified: metadata
+                            .modified()
+                            .ok()
+                            .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+                            .map(|m| m.as_secs()),
+                    })
+                })
+                .collect::<Vec<Entry>>(),
+            Source::Embedded(embedded) => {
+                if !embedded.is_dir(path) {
+                    return None;
+                }
+                embedded.entries(path)
+            }
+        };
+
+        entries.sort_by(|f, s| s.directory.cmp(&f.directory).then(f.name.cmp(&s.name)));
+        Some(entries)
+    }
+}
+
+impl From<&str> for Source {
+    fn from(value: &str) -> Self {
+        Source::Disk(value.to_string())
+    }
+}
+
+impl From<String> for Source {
+    fn from(value: String) -> Self {
+        Source::Disk(value)
+    }
+}
+
+impl From<&'static Embedded> for Source {
+    /// Debug builds read the embedded directory from disk so changes show up without a rebuild.
+    fn from(value: &'static Embedded) -> Self {
+        if cfg!(debug_assertions) {
+            Source::Disk(value.root.to_string())
+        } else {
+            Source::Embedded(value)
+        }
+    }
+}
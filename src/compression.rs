@@ -0,0 +1,676 @@
+use http_body_util::BodyExt;
+use hyper::{body::Body, header::HeaderValue, HeaderMap};
+
+use crate::{
+    encoding::{preferred, Encoding},
+    response::{empty, full, Error, Response, Result},
+};
+
+/// Encodings in order of server preference along with whether their feature is enabled
+const AVAILABLE: [(Encoding, bool); 4] = [
+    (Encoding::Brotli, cfg!(feature = "brotli")),
+    (Encoding::Zstd, cfg!(feature = "zstd")),
+    (Encoding::Gzip, cfg!(feature = "gzip")),
+    (Encoding::Deflate, cfg!(feature = "deflate")),
+];
+
+/// Content types, other than `text/*`, `*+json`, and `*+xml`, that benefit from compression
+const COMPRESSIBLE: [&str; 4] = [
+    "application/json",
+    "application/xml",
+    "application/javascript",
+    "application/wasm",
+];
+
+/// Opt-in compression of responses based on the request's `Accept-Encoding`
+///
+/// Only text like responses (html, json, templates, plain text, etc...) that are larger than the
+/// threshold are compressed. Responses that already have a `Content-Encoding` or that are a
+/// partial response are left untouched. Each encoding is behind a feature of the same name:
+/// `brotli`, `zstd`, `gzip`, and `deflate`.
+///
+/// # Example
+/// ```no_run
+/// use tela::{compression::Compression, Server};
+///
+/// #[tela::main]
+/// async fn main() {
+///     Server::new()
+///         .compression(Compression::new().threshold(512))
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Compression {
+    threshold: usize,
+    encodings: Vec<Encoding>,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression::new()
+    }
+}
+
+impl Compression {
+    /// Compress responses over 1KB with all enabled encodings
+    pub fn new() -> Self {
+        Compression {
+            threshold: 1024,
+            encodings: AVAILABLE
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(encoding, _)| *encoding)
+                .collect(),
+        }
+    }
+
+    /// Minimum size, in bytes, of a response body before it is compressed
+    pub fn threshold(mut self, bytes: usize) -> Self {
+        self.threshold = bytes;
+        self
+    }
+
+    /// Encodings that may be used, in order of preference. Encodings that aren't enabled through
+    /// their feature are ignored.
+    pub fn encodings<const SIZE: usize>(mut self, encodings: [Encoding; SIZE]) -> Self {
+        self.encodings = encodings
+            .into_iter()
+            .filter(|encoding| {
+                AVAILABLE
+                    .iter()
+                    .any(|(available, enabled)| *enabled && available == encoding)
+            })
+            .collect();
+        self
+    }
+
+    /// Compress the response if it is compressible and the client accepts one of the encodings.
+    ///
+    /// Streamed bodies, bodies without a known size, are never compressed.
+    pub(crate) async fn compress(&self, request: &HeaderMap, response: Response) -> Response {
+        if self.encodings.is_empty()
+            || !compressible(&response)
+            || response.body().size_hint().exact().is_none()
+        {
+            return response;
+        }
+
+        let (mut parts, body) = response.into_parts();
+        vary(&mut parts.headers);
+
+        let data = match body.collect().await {
+            Ok(collected) => collected.to_bytes(),
+            Err(_) => return hyper::Response::from_parts(parts, empty()),
+        };
+        if data.len() < self.threshold {
+            return hyper::Response::from_parts(parts, full(data));
+        }
+
+        let encoding = match preferred(request, &self.encodings) {
+            Some(Encoding::Identity) | None => {
+                return hyper::Response::from_parts(parts, full(data))
+            }
+            Some(encoding) => encoding,
+        };
+
+        match encode(encoding, &data) {
+            Ok(compressed) => {
+                parts.headers.insert(
+                    "Content-Encoding",
+                    HeaderValue::from_static(encoding.name()),
+                );
+                parts.headers.remove("Content-Length");
+                // Ranges are for the identity representation
+                parts.headers.remove("Accept-Ranges");
+                // The compressed body is no longer byte for byte the same
+                if let Some(etag) = parts.headers.get("ETag").and_then(|e| e.to_str().ok()) {
+                    if !etag.starts_with("W/") {
+                        if let Ok(weak) = HeaderValue::from_str(&format!("W/{}", etag)) {
+                            parts.headers.insert("ETag", weak);
+                        }
+                    }
+                }
+                hyper::Response::from_parts(parts, full(compressed))
+            }
+            Err(_) => hyper::Response::from_parts(parts, full(data)),
+        }
+    }
+}
+
+/// Add `Accept-Encoding` to the `Vary` header
+fn vary(headers: &mut HeaderMap) {
+    let vary = headers
+        .get("Vary")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+
+    match vary {
+        Some(vary)
+            if vary
+                .split(',')
+                .any(|v| v.trim().eq_ignore_ascii_case("accept-encoding") || v.trim() == "*") => {}
+        Some(vary) => {
+            if let Ok(value) = HeaderValue::from_str(&format!("{}, Accept-Encoding", vary)) {
+                headers.insert("Vary", value);
+            }
+        }
+        None => {
+            headers.insert("Vary", HeaderValue::from_static("Accept-Encoding"));
+        }
+    }
+}
+
+fn compressible(response: &Response) -> bool {
+    if response.status().is_informational()
+        || response.status() == 206
+        || response.status() == 204
+        || response.status() == 304
+        || response.headers().contains_key("Content-Encoding")
+        || response.headers().contains_key("Content-Range")
+    {
+        return false;
+    }
+
+    match response
+        .headers()
+        .get("Content-Type")
+        .and_then(|ct| ct.to_str().ok())
+    {
+        Some(content_type) => {
+            let mime = content_type
+                .split(';')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_ascii_lowercase();
+            mime.starts_with("text/")
+                || mime.ends_with("+json")
+                || mime.ends_with("+xml")
+                || COMPRESSIBLE.contains(&mime.as_str())
+        }
+        // Bodies without a content type are generated by tela; ex: templates
+        None => true,
+    }
+}
+
+fn encode(encoding: Encoding, data: &[u8]) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        #[cfg(feature = "gzip")]
+        Encoding::Gzip => {
+            use std::io::Write;
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+        #[cfg(feature = "deflate")]
+        Encoding::Deflate => {
+            use std::io::Write;
+            // HTTP deflate is the zlib format
+            let mut encoder =
+                flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+        #[cfg(feature = "brotli")]
+        Encoding::Brotli => {
+            use std::io::Write;
+            let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+            encoder.write_all(data)?;
+            Ok(encoder.into_inner())
+        }
+        #[cfg(feature = "zstd")]
+        Encoding::Zstd => zstd::stream::encode_all(data, 0),
+        encoding => {
+            let _ = data;
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("{} compression is not enabled", encoding.name()),
+            ))
+        }
+    }
+}
+
+/// Decompression of request bodies based on their `Content-Encoding`
+///
+/// Bodies are decompressed before they are given to endpoints. Bodies that decompress to more
+/// than the route's body limit are rejected with `413 Payload Too Large`, unknown or disabled
+/// encodings with `415 Unsupported Media Type`, and corrupt bodies with `400 Bad Request`.
+/// Decoding uses the same features as compression: `brotli`, `zstd`, `gzip`, and `deflate`.
+///
+/// Streamed bodies, ex: `BodyStream` and multipart, can't be decompressed and are rejected with
+/// `415 Unsupported Media Type` when they have a `Content-Encoding`.
+#[derive(Debug, Clone)]
+pub struct Decompression {
+    limit: Option<usize>,
+}
+
+impl Default for Decompression {
+    fn default() -> Self {
+        Decompression::new()
+    }
+}
+
+impl Decompression {
+    /// Decompress bodies up to the route's body limit
+    pub fn new() -> Self {
+        Decompression { limit: None }
+    }
+
+    /// Max size, in bytes, a body may decompress to. Routes with a smaller body limit use their
+    /// own limit.
+    pub fn limit(mut self, bytes: usize) -> Self {
+        self.limit = Some(bytes);
+        self
+    }
+
+    /// Decompress the body according to the request's `Content-Encoding`, up to the route's body
+    /// `limit`. On success the `Content-Encoding` and `Content-Length` headers are removed as
+    /// they no longer apply.
+    pub(crate) fn decompress(
+        &self,
+        headers: &mut HeaderMap,
+        body: &mut Vec<u8>,
+        limit: usize,
+    ) -> Result<()> {
+        let encodings = content_encodings(headers)?;
+        if encodings.is_empty() {
+            return Ok(());
+        }
+
+        let limit = self.limit.map_or(limit, |max| max.min(limit));
+        // Encodings are listed in the order they were applied
+        for encoding in encodings.iter().rev() {
+            *body = decode(*encoding, body, limit)?;
+        }
+
+        headers.remove("Content-Encoding");
+        headers.remove("Content-Length");
+        Ok(())
+    }
+
+    /// Reject a streamed body with a `Content-Encoding`, other than `identity`, as it can't be
+    /// decompressed before it reaches the endpoint.
+    pub(crate) fn check_streamed(&self, headers: &HeaderMap) -> Result<()> {
+        match content_encodings(headers)?
+            .iter()
+            .any(|encoding| *encoding != Encoding::Identity)
+        {
+            true => Err(Error::new(
+                415,
+                "Streamed request bodies can't have a Content-Encoding",
+            )),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Codings listed in the request's `Content-Encoding`, in the order they were applied
+fn content_encodings(headers: &HeaderMap) -> Result<Vec<Encoding>> {
+    match headers
+        .get("Content-Encoding")
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(value) => value
+            .split(',')
+            .map(|e| e.trim())
+            .filter(|e| !e.is_empty())
+            .map(|e| {
+                Encoding::from_name(e).ok_or_else(|| {
+                    Error::new(415, format!("Unsupported request Content-Encoding: {}", e))
+                })
+            })
+            .collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn decode(encoding: Encoding, data: &[u8], limit: usize) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    let reader: Box<dyn Read + '_> = match encoding {
+        Encoding::Identity => Box::new(data),
+        #[cfg(feature = "gzip")]
+        Encoding::Gzip => Box::new(flate2::read::MultiGzDecoder::new(data)),
+        #[cfg(feature = "deflate")]
+        Encoding::Deflate => Box::new(flate2::read::ZlibDecoder::new(data)),
+        #[cfg(feature = "brotli")]
+        Encoding::Brotli => Box::new(brotli::Decompressor::new(data, 4096)),
+        #[cfg(feature = "zstd")]
+        Encoding::Zstd => Box::new(zstd::stream::read::Decoder::new(data).map_err(|err| {
+            Error::new(400, format!("Invalid zstd request body: {}", err)).with_source(err)
+        })?),
+        #[allow(unreachable_patterns)]
+        encoding => {
+            return Err(Error::new(
+                415,
+                format!(
+                    "Request Content-Encoding {} is not enabled",
+                    encoding.name()
+                ),
+            ))
+        }
+    };
+
+    let mut decoded = Vec::new();
+    reader
+        .take(limit as u64 + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|err| {
+            Error::new(
+                400,
+                format!("Invalid {} request body: {}", encoding.name(), err),
+            )
+            .with_source(err)
+        })?;
+
+    if decoded.len() > limit {
+        return Err(Error::new(
+            413,
+            format!("Decompressed request body exceeds {} bytes", limit),
+        ));
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use http_body_util::BodyExt;
+    use hyper::HeaderMap;
+
+    use super::{compressible, vary, Compression, Decompression};
+    use crate::response::{full, Response};
+
+    fn response(content_type: Option<&str>, size: usize) -> Response {
+        let mut builder = hyper::Response::builder().header("ETag", "\"tela\"");
+        if let Some(content_type) = content_type {
+            builder = builder.header("Content-Type", content_type);
+        }
+        builder.body(full(vec![b'a'; size])).unwrap()
+    }
+
+    /// Compress a response for a request with the given `Accept-Encoding` and return the
+    /// response headers and body
+    fn compress(
+        compression: &Compression,
+        accept: Option<&str>,
+        response: Response,
+    ) -> (HeaderMap, Vec<u8>) {
+        let mut request = HeaderMap::new();
+        if let Some(accept) = accept {
+            request.insert("Accept-Encoding", accept.parse().unwrap());
+        }
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let (parts, body) = compression.compress(&request, response).await.into_parts();
+            let body = body.collect().await.unwrap().to_bytes().to_vec();
+            (parts.headers, body)
+        })
+    }
+
+    fn vary_of(existing: Option<&str>) -> String {
+        let mut headers = HeaderMap::new();
+        if let Some(existing) = existing {
+            headers.insert("Vary", existing.parse().unwrap());
+        }
+        vary(&mut headers);
+        headers.get("Vary").unwrap().to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn vary_is_merged() {
+        assert_eq!(vary_of(None), "Accept-Encoding");
+        assert_eq!(vary_of(Some("Origin")), "Origin, Accept-Encoding");
+        assert_eq!(
+            vary_of(Some("Origin, accept-encoding")),
+            "Origin, accept-encoding"
+        );
+        assert_eq!(vary_of(Some("*")), "*");
+    }
+
+    #[test]
+    fn text_like_types_are_compressible() {
+        assert!(compressible(&response(Some("text/html"), 0)));
+        assert!(compressible(&response(Some("text/css; charset=utf-8"), 0)));
+        assert!(compressible(&response(Some("application/json"), 0)));
+        assert!(compressible(&response(Some("application/problem+json"), 0)));
+        assert!(compressible(&response(Some("image/svg+xml"), 0)));
+        assert!(compressible(&response(None, 0)));
+
+        assert!(!compressible(&response(Some("image/png"), 0)));
+        assert!(!compressible(&response(
+            Some("application/octet-stream"),
+            0
+        )));
+    }
+
+    #[test]
+    fn encoded_and_partial_responses_are_not_compressible() {
+        let mut encoded = response(Some("text/html"), 0);
+        encoded
+            .headers_mut()
+            .insert("Content-Encoding", "br".parse().unwrap());
+        assert!(!compressible(&encoded));
+
+        for status in [204, 206, 304] {
+            let mut partial = response(Some("text/html"), 0);
+            *partial.status_mut() = hyper::StatusCode::from_u16(status).unwrap();
+            assert!(!compressible(&partial));
+        }
+    }
+
+    #[test]
+    fn nothing_enabled_leaves_response_untouched() {
+        let compression = Compression::new().encodings([]);
+        let (headers, body) = compress(&compression, Some("gzip, br"), response(None, 2048));
+        assert!(!headers.contains_key("Content-Encoding"));
+        assert!(!headers.contains_key("Vary"));
+        assert_eq!(body.len(), 2048);
+    }
+
+    fn encoded(encoding: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Encoding", encoding.parse().unwrap());
+        headers.insert("Content-Length", "4".parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn bodies_without_encoding_are_untouched() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Length", "4".parse().unwrap());
+        let mut body = b"tela".to_vec();
+        Decompression::new()
+            .decompress(&mut headers, &mut body, 1)
+            .unwrap();
+        assert_eq!(body, b"tela");
+        assert!(headers.contains_key("Content-Length"));
+    }
+
+    #[test]
+    fn identity_is_decoded_as_is() {
+        let mut headers = encoded("identity");
+        let mut body = b"tela".to_vec();
+        Decompression::new()
+            .decompress(&mut headers, &mut body, 4)
+            .unwrap();
+        assert_eq!(body, b"tela");
+        assert!(!headers.contains_key("Content-Encoding"));
+        assert!(!headers.contains_key("Content-Length"));
+
+        let error = Decompression::new()
+            .decompress(&mut encoded("identity"), &mut b"tela".to_vec(), 3)
+            .unwrap_err();
+        assert_eq!(error.get_status(), 413);
+    }
+
+    #[test]
+    fn unknown_encodings_are_unsupported() {
+        let error = Decompression::new()
+            .decompress(&mut encoded("compress"), &mut b"tela".to_vec(), 1024)
+            .unwrap_err();
+        assert_eq!(error.get_status(), 415);
+    }
+
+    #[test]
+    fn streamed_bodies_must_not_be_encoded() {
+        let decompression = Decompression::new();
+        assert!(decompression.check_streamed(&HeaderMap::new()).is_ok());
+        assert!(decompression.check_streamed(&encoded("identity")).is_ok());
+        for encoding in ["gzip", "br", "identity, zstd", "compress"] {
+            let error = decompression
+                .check_streamed(&encoded(encoding))
+                .unwrap_err();
+            assert_eq!(error.get_status(), 415, "{}", encoding);
+        }
+    }
+
+    #[cfg(feature = "gzip")]
+    mod gzip {
+        use std::io::{Read, Write};
+
+        use super::{compress, encoded, response, Compression, Decompression};
+        use crate::encoding::Encoding;
+
+        fn gzipped(data: &[u8]) -> Vec<u8> {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        }
+
+        #[test]
+        fn decompresses_body() {
+            let mut headers = encoded("gzip");
+            let mut body = gzipped(b"hello tela");
+            Decompression::new()
+                .decompress(&mut headers, &mut body, 1024)
+                .unwrap();
+            assert_eq!(body, b"hello tela");
+            assert!(!headers.contains_key("Content-Encoding"));
+        }
+
+        #[test]
+        fn corrupt_bodies_are_bad_requests() {
+            let error = Decompression::new()
+                .decompress(&mut encoded("gzip"), &mut b"not gzip".to_vec(), 1024)
+                .unwrap_err();
+            assert_eq!(error.get_status(), 400);
+        }
+
+        #[test]
+        fn bombs_are_capped_at_the_route_limit() {
+            // 10MB of zeros compresses to ~10KB
+            let bomb = gzipped(&vec![0; 10 * 1024 * 1024]);
+            assert!(bomb.len() < 64 * 1024);
+
+            let error = Decompression::new()
+                .decompress(&mut encoded("gzip"), &mut bomb.clone(), 1024 * 1024)
+                .unwrap_err();
+            assert_eq!(error.get_status(), 413);
+
+            // Routes with a larger limit may decompress more
+            let mut body = bomb.clone();
+            Decompression::new()
+                .decompress(&mut encoded("gzip"), &mut body, 10 * 1024 * 1024)
+                .unwrap();
+            assert_eq!(body.len(), 10 * 1024 * 1024);
+
+            // The configured limit caps every route
+            let error = Decompression::new()
+                .limit(1024)
+                .decompress(&mut encoded("gzip"), &mut bomb.clone(), 10 * 1024 * 1024)
+                .unwrap_err();
+            assert_eq!(error.get_status(), 413);
+        }
+
+        #[cfg(feature = "deflate")]
+        #[test]
+        fn chained_encodings_are_decoded_in_reverse() {
+            let mut encoder =
+                flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&gzipped(b"hello tela")).unwrap();
+            let mut body = encoder.finish().unwrap();
+
+            Decompression::new()
+                .decompress(&mut encoded("gzip, deflate"), &mut body, 1024)
+                .unwrap();
+            assert_eq!(body, b"hello tela");
+        }
+
+        fn gzip() -> Compression {
+            Compression::new().encodings([Encoding::Gzip])
+        }
+
+        #[test]
+        fn compresses_over_threshold() {
+            let (headers, body) = compress(&gzip(), Some("gzip"), response(None, 2048));
+            assert_eq!(headers["Content-Encoding"], "gzip");
+            assert_eq!(headers["Vary"], "Accept-Encoding");
+            assert_eq!(headers["ETag"], "W/\"tela\"");
+            assert!(!headers.contains_key("Content-Length"));
+
+            let mut decoded = Vec::new();
+            flate2::read::GzDecoder::new(body.as_slice())
+                .read_to_end(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, vec![b'a'; 2048]);
+        }
+
+        #[test]
+        fn threshold_is_respected() {
+            let (headers, body) = compress(&gzip(), Some("gzip"), response(None, 1023));
+            assert!(!headers.contains_key("Content-Encoding"));
+            assert_eq!(headers["Vary"], "Accept-Encoding");
+            assert_eq!(body.len(), 1023);
+
+            let compression = gzip().threshold(16);
+            let (headers, _) = compress(&compression, Some("gzip"), response(None, 16));
+            assert_eq!(headers["Content-Encoding"], "gzip");
+        }
+
+        #[test]
+        fn incompressible_types_are_skipped() {
+            let (headers, body) =
+                compress(&gzip(), Some("gzip"), response(Some("image/png"), 2048));
+            assert!(!headers.contains_key("Content-Encoding"));
+            assert!(!headers.contains_key("Vary"));
+            assert_eq!(body.len(), 2048);
+        }
+
+        #[test]
+        fn refused_encodings_are_skipped() {
+            for accept in [None, Some("gzip;q=0"), Some("br"), Some("identity;q=0")] {
+                let (headers, body) = compress(&gzip(), accept, response(None, 2048));
+                assert!(!headers.contains_key("Content-Encoding"), "{:?}", accept);
+                assert_eq!(body.len(), 2048);
+            }
+
+            let (headers, _) = compress(&gzip(), Some("identity;q=0, *"), response(None, 2048));
+            assert_eq!(headers["Content-Encoding"], "gzip");
+        }
+    }
+
+    #[cfg(all(feature = "gzip", feature = "brotli"))]
+    mod preference {
+        use super::{compress, response, Compression};
+        use crate::encoding::Encoding;
+
+        #[test]
+        fn client_quality_wins() {
+            let compression = Compression::new().encodings([Encoding::Brotli, Encoding::Gzip]);
+            let encoding = |accept| {
+                compress(&compression, Some(accept), response(None, 2048)).0["Content-Encoding"]
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            };
+
+            assert_eq!(encoding("gzip, br"), "br");
+            assert_eq!(encoding("gzip;q=1, br;q=0.5"), "gzip");
+            assert_eq!(encoding("br;q=0, gzip;q=0.1"), "gzip");
+            assert_eq!(encoding("*;q=0.5, br;q=0.1"), "gzip");
+        }
+    }
+}
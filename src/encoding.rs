@@ -0,0 +1,248 @@
+use hyper::HeaderMap;
+
+/// Content codings understood by tela
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Brotli,
+    Zstd,
+    Gzip,
+    Deflate,
+    Identity,
+}
+
+impl Encoding {
+    /// Name of the coding as used in `Accept-Encoding` and `Content-Encoding`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Zstd => "zstd",
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+            Encoding::Identity => "identity",
+        }
+    }
+
+    /// Extension of precompressed sidecar files; ex: `app.js.br`
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Encoding::Brotli => Some("br"),
+            Encoding::Zstd => Some("zst"),
+            Encoding::Gzip => Some("gz"),
+            Encoding::Deflate => None,
+            Encoding::Identity => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "br" => Some(Encoding::Brotli),
+            "zstd" => Some(Encoding::Zstd),
+            "gzip" | "x-gzip" => Some(Encoding::Gzip),
+            "deflate" => Some(Encoding::Deflate),
+            "identity" => Some(Encoding::Identity),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a header with quality values, ex: `gzip;q=0.8, br`, into `(value, quality)` pairs.
+///
+/// Values without a `q` parameter have a quality of `1.0`. Invalid quality values are treated as
+/// `0.0`.
+pub fn qualities(header: &str) -> Vec<(String, f32)> {
+    header
+        .split(',')
+        .filter_map(|part| {
+            let mut params = part.split(';');
+            let value = params.next()?.trim();
+            if value.is_empty() {
+                return None;
+            }
+
+            let quality = params
+                .filter_map(|param| param.trim().split_once('='))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case("q"))
+                .map(|(_, q)| q.trim().parse::<f32>().unwrap_or(0.0))
+                .unwrap_or(1.0);
+            Some((value.to_ascii_lowercase(), quality))
+        })
+        .collect()
+}
+
+/// Quality of a media type for parsed `Accept` ranges, from the most specific matching range.
+///
+/// `text/html` matches `text/html`, then `text/*`, then `*/*`. `None` when no range matches.
+pub fn media_quality(accept: &[(String, f32)], media: &str) -> Option<f32> {
+    let media = media.to_ascii_lowercase();
+    let kind = media.split('/').next().unwrap_or_default();
+    let find = |range: &str| {
+        accept
+            .iter()
+            .filter(|(value, _)| value == range)
+            .map(|(_, q)| *q)
+            .reduce(f32::max)
+    };
+    find(&media)
+        .or_else(|| find(&format!("{}/*", kind)))
+        .or_else(|| find("*/*"))
+}
+
+/// Pick the best media type out of `supported` for parsed `Accept` ranges.
+///
+/// Types are ranked by their quality and ties are broken by the order of `supported`. `None` is
+/// returned when nothing is acceptable.
+pub fn negotiate_media<'a>(accept: &[(String, f32)], supported: &[&'a str]) -> Option<&'a str> {
+    let mut best: Option<(&str, f32)> = None;
+    for media in supported.iter() {
+        if let Some(q) = media_quality(accept, media) {
+            if q > 0.0 && best.map(|(_, b)| q > b).unwrap_or(true) {
+                best = Some((media, q));
+            }
+        }
+    }
+    best.map(|(media, _)| media)
+}
+
+/// Pick the best media type out of `supported` for the request's `Accept` header.
+///
+/// A missing header accepts anything, so the first supported type is returned.
+pub fn negotiate<'a>(headers: &HeaderMap, supported: &[&'a str]) -> Option<&'a str> {
+    match headers.get("Accept").and_then(|a| a.to_str().ok()) {
+        Some(accept) => negotiate_media(&qualities(accept), supported),
+        None => supported.first().copied(),
+    }
+}
+
+/// Whether the request's `Accept` header prefers json over html
+pub fn prefers_json(headers: &HeaderMap) -> bool {
+    negotiate(headers, &["text/html", "application/json"]) == Some("application/json")
+}
+
+/// Pick the best coding out of `supported` for the request's `Accept-Encoding` header.
+///
+/// Codings are ranked by the quality the client gives them and ties are broken by the order of
+/// `supported`. `Encoding::Identity` is returned when nothing else is acceptable, and `None` is
+/// returned only when the client explicitly refuses identity as well.
+pub fn preferred(headers: &HeaderMap, supported: &[Encoding]) -> Option<Encoding> {
+    let accept = match headers
+        .get("Accept-Encoding")
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(accept) => qualities(accept),
+        None => return Some(Encoding::Identity),
+    };
+
+    let quality = |encoding: &Encoding| -> Option<f32> {
+        accept
+            .iter()
+            .find(|(name, _)| Encoding::from_name(name).as_ref() == Some(encoding))
+            .or_else(|| accept.iter().find(|(name, _)| name == "*"))
+            .map(|(_, q)| *q)
+    };
+
+    let mut best: Option<(Encoding, f32)> = None;
+    for encoding in supported.iter() {
+        if let Some(q) = quality(encoding) {
+            if q > 0.0 && best.map(|(_, b)| q > b).unwrap_or(true) {
+                best = Some((*encoding, q));
+            }
+        }
+    }
+
+    match best {
+        Some((encoding, _)) => Some(encoding),
+        // Identity is always acceptable unless it is refused explicitly
+        None => match quality(&Encoding::Identity) {
+            Some(q) if q <= 0.0 => None,
+            _ => Some(Encoding::Identity),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::HeaderMap;
+
+    use super::{negotiate, negotiate_media, preferred, qualities, Encoding};
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("Accept", value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn parse_qualities() {
+        assert_eq!(
+            qualities("gzip;q=0.8, BR, deflate; q=abc,, identity;level=1"),
+            vec![
+                ("gzip".to_string(), 0.8),
+                ("br".to_string(), 1.0),
+                ("deflate".to_string(), 0.0),
+                ("identity".to_string(), 1.0),
+            ]
+        );
+        assert!(qualities("").is_empty());
+    }
+
+    #[test]
+    fn most_specific_range_wins() {
+        let ranges = qualities("text/*;q=0.5, text/html;q=0.1, */*;q=0.2");
+        assert_eq!(
+            negotiate_media(&ranges, &["text/html", "text/plain", "application/json"]),
+            Some("text/plain")
+        );
+    }
+
+    #[test]
+    fn ties_keep_the_server_order() {
+        let ranges = qualities("application/json, text/html");
+        assert_eq!(
+            negotiate_media(&ranges, &["text/html", "application/json"]),
+            Some("text/html")
+        );
+    }
+
+    #[test]
+    fn refused_types_are_not_picked() {
+        let ranges = qualities("text/html;q=0, application/xml");
+        assert_eq!(
+            negotiate_media(&ranges, &["text/html", "application/json"]),
+            None
+        );
+    }
+
+    #[test]
+    fn missing_accept_takes_the_first_type() {
+        assert_eq!(
+            negotiate(&HeaderMap::new(), &["application/json", "text/html"]),
+            Some("application/json")
+        );
+        assert_eq!(
+            negotiate(
+                &accept("text/html;q=0.5, application/json"),
+                &["text/html", "application/json"]
+            ),
+            Some("application/json")
+        );
+    }
+
+    #[test]
+    fn identity_is_the_fallback_coding() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Accept-Encoding", "gzip;q=0".parse().unwrap());
+        assert_eq!(
+            preferred(&headers, &[Encoding::Gzip]),
+            Some(Encoding::Identity)
+        );
+
+        headers.insert("Accept-Encoding", "identity;q=0".parse().unwrap());
+        assert_eq!(preferred(&headers, &[Encoding::Gzip]), None);
+
+        headers.insert("Accept-Encoding", "br, gzip;q=0.9".parse().unwrap());
+        assert_eq!(
+            preferred(&headers, &[Encoding::Gzip, Encoding::Brotli]),
+            Some(Encoding::Brotli)
+        );
+    }
+}
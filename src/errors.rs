@@ -1,10 +1,15 @@
-use std::backtrace::Backtrace;
+use std::{backtrace::Backtrace, collections::HashMap};
 
-use bytes::Bytes;
-use http_body_util::Full;
-use hyper::{Method, Uri};
+use hyper::{HeaderMap, Method, Uri};
 use phf::phf_map;
 
+#[cfg(not(debug_assertions))]
+use crate::response::empty;
+#[cfg(debug_assertions)]
+use crate::response::full;
+use crate::response::Response;
+use crate::response::WithReason;
+
 /// Default http error messages
 pub static MESSAGES: phf::Map<u16, &'static str> = phf_map! {
     100u16 => "Continue",
@@ -76,81 +81,312 @@ pub static MESSAGES: phf::Map<u16, &'static str> = phf_map! {
     511u16 => "Network Authentication Required",
 };
 
+/// Details of the matched route shown on the debug error page
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+pub(crate) struct RouteInfo {
+    pub pattern: String,
+    pub handler: String,
+    pub captures: HashMap<String, String>,
+}
+
+/// Extra details about the request and error shown on the debug error page
+#[derive(Default)]
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+pub(crate) struct Details<'a> {
+    pub headers: Option<&'a HeaderMap>,
+    pub route: Option<RouteInfo>,
+    /// Backtrace from where the error happened. The trace is left off the page when missing.
+    pub backtrace: Option<&'a Backtrace>,
+}
+
 pub fn default_error_page(
     code: &u16,
     reason: &String,
     method: &Method,
     uri: &Uri,
     body: String,
-) -> hyper::Response<Full<Bytes>> {
+) -> Response {
+    error_page(code, reason, method, uri, body, Details::default())
+}
+
+/// Frame of a backtrace along with whether it is from the user's code or a dependency
+#[cfg(debug_assertions)]
+struct Frame {
+    function: String,
+    file: Option<String>,
+    line: Option<usize>,
+}
+
+#[cfg(debug_assertions)]
+impl Frame {
+    fn is_user(&self) -> bool {
+        match &self.file {
+            Some(file) => {
+                let file = file.replace('\\', "/");
+                !file.starts_with("/rustc/")
+                    && !file.contains("/.cargo/")
+                    && !file.contains("/.rustup/")
+            }
+            None => false,
+        }
+    }
+
+    /// Lines around the frame's location with the location highlighted
+    fn source(&self) -> Option<String> {
+        let (file, line) = (self.file.as_ref()?, self.line?);
+        let source = std::fs::read_to_string(file).ok()?;
+        let first = line.saturating_sub(4);
+        Some(
+            source
+                .lines()
+                .enumerate()
+                .skip(first)
+                .take(7)
+                .map(|(i, text)| {
+                    let text = format!("{:>5} | {}", i + 1, escape(text));
+                    match i + 1 == line {
+                        true => format!("<span class=\"current\">{}</span>", text),
+                        false => format!("{}\n", text),
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Parse the frames out of a rendered backtrace
+#[cfg(debug_assertions)]
+fn frames(backtrace: &Backtrace) -> Vec<Frame> {
+    let mut frames: Vec<Frame> = Vec::new();
+    for line in backtrace.to_string().lines() {
+        let line = line.trim();
+        if let Some(location) = line.strip_prefix("at ") {
+            if let Some(frame) = frames.last_mut() {
+                let mut parts = location.rsplitn(3, ':');
+                let (_column, line, file) = (parts.next(), parts.next(), parts.next());
+                match (file, line.and_then(|l| l.parse::<usize>().ok())) {
+                    (Some(file), Some(line)) => {
+                        frame.file = Some(file.to_string());
+                        frame.line = Some(line);
+                    }
+                    _ => frame.file = Some(location.to_string()),
+                }
+            }
+        } else if let Some((index, function)) = line.split_once(": ") {
+            if index.chars().all(|c| c.is_ascii_digit()) {
+                frames.push(Frame {
+                    function: function.to_string(),
+                    file: None,
+                    line: None,
+                });
+            }
+        }
+    }
+    frames
+}
+
+#[cfg(debug_assertions)]
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Rows of a two column table
+#[cfg(debug_assertions)]
+fn rows<'a, I: Iterator<Item = (&'a str, String)>>(items: I) -> String {
+    let rows = items
+        .map(|(key, value)| {
+            format!(
+                "<tr><td>{}</td><td>{}</td></tr>",
+                escape(key),
+                escape(&value)
+            )
+        })
+        .collect::<String>();
+    match rows.is_empty() {
+        true => "<tr><td colspan=\"2\">None</td></tr>".to_string(),
+        false => rows,
+    }
+}
+
+pub(crate) fn error_page(
+    code: &u16,
+    reason: &str,
+    method: &Method,
+    uri: &Uri,
+    body: String,
+    details: Details,
+) -> Response {
     #[cfg(debug_assertions)]
     let styles = r#"
-*{box-sizing:border-box}body{padding:.5rem;margin:0;min-height:100vh;min-height:100dvh;display:flex;justify-content:center;align-items:center}#overlay{color:#000;border:1px solid #9e9e9e;background:#b8b6b6;display:flex;flex-direction:column;width:97%;min-height:95vh;min-height:95dvh;height:95%;border-radius:.5rem;box-shadow:rgba(0,0,0,0.25) 0 54px 55px,rgba(0,0,0,0.12) 0 -12px 30px,rgba(0,0,0,0.12) 0 4px 6px,rgba(0,0,0,0.17) 0 12px 13px,rgba(0,0,0,0.09) 0 -3px 5px}h1{font-size:2.65rem;text-align:center;margin:.5rem}h2{font-size:2.441rem}h3{font-size:1.953rem}h4{font-size:1.563rem}h5{font-size:1.25rem}small,.text_small{font-size:.8rem}details summary{cursor:pointer}hr{border:1px solid rgba(0,0,0,0.5)}details summary>*{display:inline}summary{background-color:rgba(200,15,50,0.5);padding-block:.25rem;padding-inline:.5rem;font-weight:700}summary::marker{color:rgba(200,15,50,0.50)}details{border:1px solid rgba(200,15,50,0.75);border-radius:.25rem;display:flex;gap:.5rem;width:85%;margin-inline:auto;margin-block:1rem;font-family:Arial,sans-serif;font-size:1.1rem}details>#body{background-color:rgba(200,15,50,0.25);padding:1rem;display:flex;flex-direction:column;gap:.5rem}.path{background-color:rgba(0,0,0,.5);padding:.2rem .35rem;border-radius:.2rem}details>#body>div{width:80%;color:#fff;max-width:95ch;margin-inline:auto;border:1px solid rgba(0,0,0,.5);background-color:rgba(0,0,0,.25);display:flex;flex-wrap:wrap}details>#body>div>span:first-child{display:inline-block;background:#000;padding:.5rem;width:40%;display:flex;align-items:center;justify-content:center}details>#body>div>span:last-child{display:inline-block;text-align:center;padding:.5rem;width:60%;max-height:6rem;overflow:auto}details>#body>div>div:first-child{display:inline-block;text-align:center;background:#000;padding:.5rem;width:100%;max-height:15rem;overflow-y:auto}details>#body>div>pre{padding:1rem;width:100%;overflow:auto;max-height:20rem}table{color:#fff;width:100%;border:1px solid #000;border-collapse:collapse}thead{background:#000}tbody{padding:.5rem;background-color:rgba(0,0,0,.25)}td{padding-block:.5rem;text-align:center}#trace{border:1px solid rgba(200,15,50,0.75);box-sizing:border-box;border-radius:.25rem;height:100%;max-height:27rem;width:85%;margin-inline:auto;overflow:auto;background-color:rgba(200,15,50,0.25)}@media(prefers-color-scheme: dark){#overlay{background:#1c1c1c;border:1px solid #171717;color:#fff}details>#body>div>div:last-child{color:#fff}html{background:#333}}
+*{box-sizing:border-box}body{padding:.5rem;margin:0;min-height:100vh;min-height:100dvh;display:flex;justify-content:center;align-items:center}#overlay{color:#000;border:1px solid #9e9e9e;background:#b8b6b6;display:flex;flex-direction:column;width:97%;min-height:95vh;min-height:95dvh;height:95%;border-radius:.5rem;box-shadow:rgba(0,0,0,0.25) 0 54px 55px,rgba(0,0,0,0.12) 0 -12px 30px,rgba(0,0,0,0.12) 0 4px 6px,rgba(0,0,0,0.17) 0 12px 13px,rgba(0,0,0,0.09) 0 -3px 5px}h1{font-size:2.65rem;text-align:center;margin:.5rem}h2{font-size:2.441rem}h3{font-size:1.953rem}h4{font-size:1.563rem}h5{font-size:1.25rem}small,.text_small{font-size:.8rem}details summary{cursor:pointer}hr{border:1px solid rgba(0,0,0,0.5)}details summary>*{display:inline}summary{background-color:rgba(200,15,50,0.5);padding-block:.25rem;padding-inline:.5rem;font-weight:700}summary::marker{color:rgba(200,15,50,0.50)}details{border:1px solid rgba(200,15,50,0.75);border-radius:.25rem;display:flex;gap:.5rem;width:85%;margin-inline:auto;margin-block:1rem;font-family:Arial,sans-serif;font-size:1.1rem}details>#body{background-color:rgba(200,15,50,0.25);padding:1rem;display:flex;flex-direction:column;gap:.5rem}.path{background-color:rgba(0,0,0,.5);padding:.2rem .35rem;border-radius:.2rem}details>#body>div{width:80%;color:#fff;max-width:95ch;margin-inline:auto;border:1px solid rgba(0,0,0,.5);background-color:rgba(0,0,0,.25);display:flex;flex-wrap:wrap}details>#body>div>span:first-child{display:inline-block;background:#000;padding:.5rem;width:40%;display:flex;align-items:center;justify-content:center}details>#body>div>span:last-child{display:inline-block;text-align:center;padding:.5rem;width:60%;max-height:6rem;overflow:auto}details>#body>div>div:first-child{display:inline-block;text-align:center;background:#000;padding:.5rem;width:100%;max-height:15rem;overflow-y:auto}details>#body>div>pre{padding:1rem;width:100%;overflow:auto;max-height:20rem}table{color:#fff;width:100%;border:1px solid #000;border-collapse:collapse}thead{background:#000}tbody{padding:.5rem;background-color:rgba(0,0,0,.25)}td{padding-block:.5rem;text-align:center}#trace{border:1px solid rgba(200,15,50,0.75);box-sizing:border-box;border-radius:.25rem;height:100%;max-height:27rem;width:85%;margin-inline:auto;overflow:auto;background-color:rgba(200,15,50,0.25)}@media(prefers-color-scheme: dark){#overlay{background:#1c1c1c;border:1px solid #171717;color:#fff}details>#body>div>div:last-child{color:#fff}html{background:#333}}table{table-layout:fixed}td{overflow-wrap:anywhere;padding-inline:.5rem}#deps-toggle{width:85%;margin-inline:auto;font-family:Arial,sans-serif}#deps:not(:checked)~#trace .dependency{display:none}#trace .frame{width:auto;margin:.25rem;border-color:rgba(0,0,0,.35);display:block}#trace .frame summary{background-color:rgba(0,0,0,.25);font-weight:400}#trace .frame.user summary{background-color:rgba(200,15,50,0.5)}#trace .frame small{opacity:.75;margin-left:.5rem}#trace .source{margin:0;padding:.5rem;overflow:auto;background:rgba(0,0,0,.5);color:#fff}#trace .source .current{background:rgba(200,15,50,0.6);display:block}
     "#;
 
     #[cfg(debug_assertions)]
-    std::env::set_var("RUST_BACKTRACE", "1");
-    let bcktrc: String = Backtrace::capture()
-        .to_string()
-        .replace("<", "&lt;")
-        .replace(">", "&gt;");
-    #[cfg(debug_assertions)]
-    std::env::set_var("RUST_BACKTRACE", "0");
+    {
+        let trace = details
+            .backtrace
+            .map(frames)
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                let user = frame.is_user();
+                let location = match (&frame.file, frame.line) {
+                    (Some(file), Some(line)) => format!("{}:{}", file, line),
+                    (Some(file), None) => file.clone(),
+                    _ => String::new(),
+                };
+                let source = match user {
+                    true => frame
+                        .source()
+                        .map(|source| format!("<pre class=\"source\">{}</pre>", source))
+                        .unwrap_or_default(),
+                    false => String::new(),
+                };
+                format!(
+                    "<details class=\"frame {}\"{}><summary><code>{}: {}</code><small>{}</small></summary>{}</details>",
+                    if user { "user" } else { "dependency" },
+                    if user && !source.is_empty() { " open" } else { "" },
+                    i,
+                    escape(&frame.function),
+                    escape(&location),
+                    source
+                )
+            })
+            .collect::<String>();
+        let trace = match trace.is_empty() {
+            true => String::new(),
+            false => format!(
+                "<input type=\"checkbox\" id=\"deps\"/><label id=\"deps-toggle\" for=\"deps\">Show dependency frames</label><div id=\"trace\">{}</div>",
+                trace
+            ),
+        };
 
-    #[cfg(debug_assertions)]
-    return hyper::Response::builder()
-        .status(code.clone())
-        .header("Tela-Reason", reason)
-        .header("Content-Type", "text/html")
-        .body(Full::new(Bytes::from(html_to_string_macro::html! {
-        <!DOCTYPE html>
-        <html lang="en">
+        let headers = details
+            .headers
+            .map(|headers| {
+                rows(headers.iter().map(|(key, value)| {
+                    (
+                        key.as_str(),
+                        value.to_str().unwrap_or("<binary>").to_string(),
+                    )
+                }))
+            })
+            .unwrap_or_else(|| rows(std::iter::empty()));
+        let cookies = rows(
+            details
+                .headers
+                .into_iter()
+                .flat_map(|headers| headers.get_all("Cookie"))
+                .filter_map(|cookie| cookie.to_str().ok())
+                .flat_map(|cookie| cookie.split(';'))
+                .filter_map(|cookie| cookie.trim().split_once('='))
+                .map(|(name, value)| (name, value.to_string())),
+        );
+        let (pattern, handler, captures) = match &details.route {
+            Some(route) => {
+                let mut captures = route.captures.iter().collect::<Vec<_>>();
+                captures.sort();
+                (
+                    escape(&route.pattern),
+                    escape(&route.handler),
+                    rows(captures.into_iter().map(|(k, v)| (k.as_str(), v.clone()))),
+                )
+            }
+            None => (
+                "None".to_string(),
+                "None".to_string(),
+                rows(std::iter::empty()),
+            ),
+        };
 
-        <head>
-            <meta charset="UTF-8"/>
-            <meta name="viewport" content="width=device-width, initial-scale=1"/>
-            <style>
-                {styles}
-            </style>
-        </head>
+        hyper::Response::builder()
+            .status(*code)
+            .tela_reason(reason)
+            .header("Content-Type", "text/html")
+            .body(full(html_to_string_macro::html! {
+            <!DOCTYPE html>
+            <html lang="en">
 
-        <body>
-            <div id="overlay">
-                <h1>{code}" "{StatusCode::from(code.clone()).message()}</h1>
-                <details open>
-                    <summary>
-                        <h4>"Unhandled Error:"</h4>
-                    </summary>
-                    <div id="body">
-                        <strong>{reason}</strong>
-                        <div><span>"Method"</span><span>{method}</span></div>
-                        <div><span>"Status"</span><span>{code}</span></div>
-                        <div><span>"URI"</span><span><span class="path">{uri.path()}</span></span></div>
-                        <div><span>"Query"</span><span>{uri.query().unwrap_or("")}</span></div>
-                        <div>
-                            <div>"Body"</div>
-                            <div>{body}</div>
+            <head>
+                <meta charset="UTF-8"/>
+                <meta name="viewport" content="width=device-width, initial-scale=1"/>
+                <style>
+                    {styles}
+                </style>
+            </head>
+
+            <body>
+                <div id="overlay">
+                    <h1>{code}" "{StatusCode::from(*code).message()}</h1>
+                    <details open>
+                        <summary>
+                            <h4>"Unhandled Error:"</h4>
+                        </summary>
+                        <div id="body">
+                            <strong>{escape(reason)}</strong>
+                            <div><span>"Method"</span><span>{escape(method.as_str())}</span></div>
+                            <div><span>"Status"</span><span>{code}</span></div>
+                            <div><span>"URI"</span><span><span class="path">{escape(uri.path())}</span></span></div>
+                            <div><span>"Query"</span><span>{escape(uri.query().unwrap_or(""))}</span></div>
+                            <div>
+                                <div>"Body"</div>
+                                <div>{escape(&body)}</div>
+                            </div>
+                        </div>
+                    </details>
+                    <details>
+                        <summary>
+                            <h4>"Route"</h4>
+                        </summary>
+                        <div id="body">
+                            <div><span>"Pattern"</span><span><span class="path">{pattern}</span></span></div>
+                            <div><span>"Handler"</span><span>{handler}</span></div>
+                            <div>
+                                <div>"Captures"</div>
+                                <table><tbody>{captures}</tbody></table>
+                            </div>
+                        </div>
+                    </details>
+                    <details>
+                        <summary>
+                            <h4>"Headers"</h4>
+                        </summary>
+                        <div id="body">
+                            <div><table><tbody>{headers}</tbody></table></div>
                         </div>
-                    </div>
-                </details>
-                <div id="trace">
-                    <pre style="padding: .5rem">
-{bcktrc}
-                    </pre>
+                    </details>
+                    <details>
+                        <summary>
+                            <h4>"Cookies"</h4>
+                        </summary>
+                        <div id="body">
+                            <div><table><tbody>{cookies}</tbody></table></div>
+                        </div>
+                    </details>
+                    {trace}
                 </div>
-            </div>
-        </body>
+            </body>
 
-        </html>
-                })))
-        .unwrap();
+            </html>
+                    }))
+            .unwrap()
+    }
 
     #[cfg(not(debug_assertions))]
-    return hyper::Response::builder()
-        .status(code.clone())
-        .header("Tela-Reason", reason)
-        .body(Full::new(Bytes::new()))
-        .unwrap();
+    {
+        let _ = (method, uri, body, details);
+        hyper::Response::builder()
+            .status(*code)
+            .tela_reason(reason)
+            .body(empty())
+            .unwrap()
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -302,3 +538,9 @@ impl From<u16> for StatusCode {
         }
     }
 }
+
+impl From<StatusCode> for u16 {
+    fn from(value: StatusCode) -> Self {
+        value as u16
+    }
+}
@@ -2,13 +2,21 @@ pub(crate) mod errors;
 mod router;
 mod server;
 
+pub mod assets;
+pub mod compression;
+pub mod encoding;
+pub mod panic;
 pub mod prelude;
 pub mod request;
 pub mod response;
 pub mod support;
 pub mod uri;
+pub mod validation;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
 pub use errors::StatusCode;
+pub use response::Error;
 pub use router::Router;
 pub use server::Server;
 
@@ -17,6 +25,7 @@ pub mod bump {
     pub use bytes;
     pub use http_body_util;
     pub use hyper;
+    pub use regex;
     pub use serde;
     pub use serde_json;
     pub use tokio;
@@ -0,0 +1,143 @@
+use std::{
+    backtrace::Backtrace,
+    cell::RefCell,
+    fmt::Display,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{Arc, Once},
+};
+
+use hyper::{Method, Uri};
+
+use crate::response::Error;
+
+/// Callback given every panic caught while handling a request
+pub type PanicHook = Arc<dyn Fn(&Panic) + Send + Sync>;
+
+/// Location and backtrace of a panic
+type Origin = (Option<String>, Option<Arc<Backtrace>>);
+
+thread_local! {
+    /// Origin of the last panic on this thread, set by the panic hook
+    static ORIGIN: RefCell<Origin> = const { RefCell::new((None, None)) };
+}
+
+static INSTALL: Once = Once::new();
+
+/// Record where panics happen while still running the previous hook
+fn install() {
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let backtrace = match cfg!(debug_assertions) {
+                true => Some(Arc::new(Backtrace::force_capture())),
+                false => None,
+            };
+            ORIGIN.with(|origin| {
+                *origin.borrow_mut() = (info.location().map(|l| l.to_string()), backtrace);
+            });
+            previous(info);
+        }));
+    });
+}
+
+/// Panic caught while an endpoint or catch handler was running
+///
+/// The panic is answered as a `500 Internal Server Error`. Use `Server::on_panic` to report them.
+///
+/// # Example
+/// ```no_run
+/// use tela::{prelude::*, Server};
+///
+/// #[tela::main]
+/// async fn main() {
+///     Server::new()
+///         .on_panic(|panic| {
+///             eprintln!("{} {} {}", panic.method(), panic.uri().path(), panic);
+///         })
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Panic {
+    message: String,
+    location: Option<String>,
+    backtrace: Option<Arc<Backtrace>>,
+    method: Method,
+    uri: Uri,
+}
+
+impl Panic {
+    /// Message the handler panicked with
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// File, line, and column of the panic, ex: `src/main.rs:10:5`
+    pub fn location(&self) -> Option<&str> {
+        self.location.as_deref()
+    }
+
+    /// Backtrace from where the handler panicked. Only captured in debug builds.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_deref()
+    }
+
+    /// Method of the request that panicked
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// URI of the request that panicked
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+}
+
+impl Display for Panic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "panicked at {}: {}", location, self.message),
+            None => write!(f, "panicked: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for Panic {}
+
+impl From<Panic> for Error {
+    fn from(panic: Panic) -> Self {
+        let backtrace = panic.backtrace.clone();
+        let error = Error::new(500, "Handler panicked").with_backtrace(backtrace);
+        // The message and location can reveal internals so they are only part of the error's
+        // reason in debug builds. `Server::on_panic` is given them either way.
+        match cfg!(debug_assertions) {
+            true => error.with_source(panic),
+            false => error,
+        }
+    }
+}
+
+/// Run a handler, catching any panic instead of unwinding the connection
+pub(crate) fn catch_panic<T, F: FnOnce() -> T>(
+    method: &Method,
+    uri: &Uri,
+    handler: F,
+) -> Result<T, Box<Panic>> {
+    install();
+    catch_unwind(AssertUnwindSafe(handler)).map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+        let (location, backtrace) = ORIGIN.with(|origin| origin.take());
+        Box::new(Panic {
+            message,
+            location,
+            backtrace,
+            method: method.clone(),
+            uri: uri.clone(),
+        })
+    })
+}
@@ -1,19 +1,22 @@
 pub use crate::request::{Catch, Endpoint, ToParam};
-pub use crate::response::{template::TemplateEngine, Result, ToErrorResponse, ToResponse};
+pub use crate::response::{
+    template::TemplateEngine, ResponseExt, Result, ToErrorResponse, ToResponse,
+};
 pub use crate::{context, group, response, template};
 pub use html_to_string_macro::html as html_raw;
 pub use serde_json::json;
 pub use tela_macros::{
-    catch, connect, delete, get, head, html, options, patch, post, put, request, trace,
+    catch, connect, delete, embed_assets, get, head, html, options, patch, post, put, request,
+    trace,
 };
 
 #[macro_export]
 macro_rules! response {
     ($code: literal, $message: literal) => {
-        Err(($code, $message.to_string()))
+        Err(($code, $message.to_string()).into())
     };
     ($code: expr, $message: literal) => {
-        Err(($code as u16, $message.to_string()))
+        Err(($code as u16, $message.to_string()).into())
     };
     ($result: expr) => {
         Ok($result)
@@ -0,0 +1,58 @@
+use crate::{
+    encoding::{media_quality, negotiate_media, qualities},
+    response::Result,
+};
+
+use super::{RequestData, ToParam};
+
+/// Media ranges of the request's `Accept` header along with their quality
+///
+/// A missing header accepts anything.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, request::Accept};
+///
+/// #[get("/report")]
+/// fn report(accept: Accept) -> String {
+///     match accept.negotiate(&["text/csv", "text/plain"]) {
+///         Some("text/csv") => "name,total\nreport,10".to_string(),
+///         _ => "report: 10".to_string(),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Accept(pub Vec<(String, f32)>);
+
+impl Accept {
+    /// Quality the client gives a media type; `0.0` when it isn't acceptable
+    pub fn quality(&self, media: &str) -> f32 {
+        media_quality(&self.0, media).unwrap_or(0.0)
+    }
+
+    pub fn accepts(&self, media: &str) -> bool {
+        self.quality(media) > 0.0
+    }
+
+    /// Best media type out of `supported`. Ties are broken by the order of `supported`.
+    pub fn negotiate<'a>(&self, supported: &[&'a str]) -> Option<&'a str> {
+        negotiate_media(&self.0, supported)
+    }
+}
+
+impl Default for Accept {
+    fn default() -> Self {
+        Accept(vec![("*/*".to_string(), 1.0)])
+    }
+}
+
+impl ToParam<Accept> for RequestData {
+    fn to_param(&mut self) -> Result<Accept> {
+        Ok(self
+            .3
+            .get("Accept")
+            .and_then(|accept| accept.to_str().ok())
+            .map(|accept| Accept(qualities(accept)))
+            .unwrap_or_default())
+    }
+}
@@ -1,27 +1,63 @@
-use crate::response::Result;
-use serde::Deserialize;
+use crate::response::{Error, Result};
+use serde::de::DeserializeOwned;
+
+use super::form::from_form;
 
 pub trait IntoBody {
-    fn into_body(body: &str) -> Result<Body<Self>>
+    fn into_body(body: &[u8], content_type: Option<&str>) -> Result<Body<Self>>
     where
         Self: Sized;
 }
 
+/// Extractor that deserializes the request body based on its `Content-Type`
+///
+/// - `application/json` and `*+json` are parsed as JSON
+/// - `application/x-www-form-urlencoded` is parsed like [`Form`](super::Form)
+/// - `text/plain` is parsed with serde_plain, ex: `Body<i32>` or `Body<String>`
+/// - Without a `Content-Type` the body is parsed as JSON and then as plain text
+///
+/// Other content types are rejected with `415 Unsupported Media Type`; use the `Bytes` or
+/// `Vec<u8>` extractors for raw bodies. Bodies that don't match `T` are rejected with
+/// `422 Unprocessable Entity` along with where parsing failed.
 #[derive(Debug, Clone, Copy)]
 pub struct Body<T: IntoBody>(pub T);
 
-impl<'a, T: Deserialize<'a>> IntoBody for T {
-    fn into_body(body: &str) -> Result<Body<Self>>
+fn from_json<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    serde_json::from_slice::<T>(body)
+        .map_err(|err| Error::new(422, format!("Invalid json body: {}", err)).with_source(err))
+}
+
+fn from_text<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    let body = std::str::from_utf8(body).map_err(|err| {
+        Error::new(400, format!("Body is not valid UTF-8: {}", err)).with_source(err)
+    })?;
+    serde_plain::from_str::<T>(body)
+        .map_err(|err| Error::new(422, format!("Invalid text body: {}", err)).with_source(err))
+}
+
+impl<T: DeserializeOwned> IntoBody for T {
+    fn into_body(body: &[u8], content_type: Option<&str>) -> Result<Body<Self>>
     where
         Self: Sized,
     {
-        let body = body.to_string();
-        match serde_json::from_str::<T>(Box::leak(body.clone().into_boxed_str())) {
-            Ok(result) => Ok(Body(result)),
-            Err(_) => match serde_plain::from_str::<T>(Box::leak(body.into_boxed_str())) {
-                Ok(result) => Ok(Body(result)),
-                Err(_) => Err((500, "Failed to parse body from request".to_string())),
-            },
+        match content_type {
+            Some("application/json") => from_json(body),
+            Some(ct) if ct.ends_with("+json") => from_json(body),
+            Some("application/x-www-form-urlencoded") => from_form(body),
+            Some("text/plain") => from_text(body),
+            Some(ct) => Err(Error::new(
+                415,
+                format!(
+                    "Unsupported request body Content-Type {}; expected json, form, or plain text",
+                    ct
+                ),
+            )),
+            None => from_json(body).or_else(|_| {
+                from_text(body).map_err(|_| {
+                    Error::new(422, "Failed to parse request body as json or plain text")
+                })
+            }),
         }
+        .map(Body)
     }
 }
@@ -0,0 +1,146 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_util::StreamExt;
+use hyper::body::{Body as HttpBody, Incoming};
+use tokio::runtime::Handle;
+
+use crate::response::{Error, Result};
+
+/// How an endpoint reads the request body
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BodyUsage {
+    /// The body is never read
+    Ignored,
+    /// The body is read into memory before the endpoint is called
+    Buffered,
+    /// The body is given to the endpoint as a [`BodyStream`]
+    Streamed,
+}
+
+/// Run a future to completion from a synchronous endpoint
+///
+/// Endpoints with a streamed body run on the blocking thread pool, where this waits without
+/// holding up the connection that delivers the body.
+pub(crate) fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    match Handle::try_current() {
+        Ok(handle) => Ok(tokio::task::block_in_place(|| handle.block_on(future))),
+        Err(_) => Err(Error::new(
+            500,
+            "Reading the request body from an endpoint requires a tokio runtime",
+        )),
+    }
+}
+
+/// Request body that is read chunk by chunk instead of being buffered in memory
+///
+/// The chunks are the raw bytes that were sent. Bodies with a `Content-Encoding` can't be decoded
+/// chunk by chunk, so they are rejected with `415 Unsupported Media Type`. Use it as a `Stream`
+/// from async code or read it with [`BodyStream::chunk`] from the endpoint itself. Reading past
+/// the body limit gives a `413 Payload Too Large` error and a broken connection gives a
+/// `400 Bad Request` error.
+///
+/// Endpoints that take a `BodyStream` run on tokio's blocking thread pool so
+/// [`BodyStream::chunk`] can wait for the body while the connection keeps reading it.
+///
+/// # Example
+/// ```
+/// use std::io::Write;
+/// use tela::{prelude::*, request::BodyStream};
+///
+/// #[post("/upload")]
+/// fn upload(mut body: BodyStream) -> Result<String> {
+///     let mut file = std::fs::File::create("upload.bin")?;
+///     let mut size = 0;
+///     while let Some(chunk) = body.chunk() {
+///         let chunk = chunk?;
+///         size += chunk.len();
+///         file.write_all(&chunk)?;
+///     }
+///     Ok(format!("Received {} bytes", size))
+/// }
+/// ```
+#[derive(Debug)]
+pub struct BodyStream {
+    body: Incoming,
+    limit: usize,
+    read: usize,
+}
+
+impl BodyStream {
+    pub(crate) fn new(body: Incoming, limit: usize) -> Self {
+        BodyStream {
+            body,
+            limit,
+            read: 0,
+        }
+    }
+
+    /// Wait for the next chunk of the body. Returns `None` once the whole body is read.
+    ///
+    /// This blocks the current thread while waiting so it should only be used from endpoints; use
+    /// the body as a `Stream` in async code.
+    pub fn chunk(&mut self) -> Option<Result<Bytes>> {
+        match block_on(self.next()) {
+            Ok(chunk) => chunk,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+impl futures_util::Stream for BodyStream {
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.read > self.limit {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            match futures_util::ready!(Pin::new(&mut self.body).poll_frame(cx)) {
+                Some(Ok(frame)) => {
+                    // Trailers aren't part of the body
+                    if let Ok(data) = frame.into_data() {
+                        self.read += data.len();
+                        if self.read > self.limit {
+                            return Poll::Ready(Some(Err(Error::new(
+                                413,
+                                format!("Request body exceeds {} bytes", self.limit),
+                            ))));
+                        }
+                        return Poll::Ready(Some(Ok(data)));
+                    }
+                }
+                Some(Err(err)) => {
+                    return Poll::Ready(Some(Err(Error::new(
+                        400,
+                        format!("Failed to read request body: {}", err),
+                    )
+                    .with_source(err))))
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+/// Request body waiting to be taken by a `BodyStream` extractor. It is shared so it can be stored
+/// in the request extensions, which must be `Clone`.
+#[derive(Clone)]
+pub(crate) struct PendingBody(Arc<Mutex<Option<BodyStream>>>);
+
+impl PendingBody {
+    pub(crate) fn new(stream: BodyStream) -> Self {
+        PendingBody(Arc::new(Mutex::new(Some(stream))))
+    }
+
+    /// Take the body. Only the first extractor gets it.
+    pub(crate) fn take(&self) -> Option<BodyStream> {
+        self.0.lock().ok()?.take()
+    }
+}
@@ -0,0 +1,456 @@
+use std::{collections::HashMap, fmt};
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess,
+    Visitor,
+};
+use serde::Deserialize;
+
+use crate::response::{Error, Result};
+
+use super::{media_type, RequestData, ToParam};
+
+/// Extractor for `application/x-www-form-urlencoded` bodies, ex: a `<form method="post">`
+///
+/// Nested keys, `user[name]=tela`, and repeated fields, `tag=a&tag=b` or `tag[]=a&tag[]=b`, are
+/// supported through serde_qs. A sequence field also takes a single `tag=a`. Bodies with another content type are rejected with
+/// `415 Unsupported Media Type` and bodies that don't match `T` with `422 Unprocessable Entity`.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+/// use tela::{prelude::*, request::Form};
+///
+/// #[derive(Deserialize)]
+/// struct Signup {
+///     email: String,
+///     interests: Vec<String>,
+/// }
+///
+/// #[post("/signup")]
+/// fn signup(Form(form): Form<Signup>) -> String {
+///     format!("{} likes {}", form.email, form.interests.join(", "))
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Form<T>(pub T);
+
+/// Give repeated keys without brackets an index so serde_qs collects them into a sequence
+fn index_repeated(body: &str) -> String {
+    fn key(pair: &str) -> &str {
+        pair.split('=').next().unwrap_or("")
+    }
+    let bare = |key: &str| !key.contains('[') && !key.to_ascii_lowercase().contains("%5b");
+
+    let pairs: Vec<&str> = body.split('&').filter(|p| !p.is_empty()).collect();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for pair in pairs.iter() {
+        *counts.entry(key(pair)).or_default() += 1;
+    }
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    pairs
+        .iter()
+        .map(|pair| {
+            let name = key(pair);
+            if counts[name] < 2 || !bare(name) {
+                return pair.to_string();
+            }
+
+            let index = seen.entry(name).or_default();
+            *index += 1;
+            format!("{}[{}]{}", name, *index - 1, &pair[name.len()..])
+        })
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+/// Wraps every deserializer, visitor, and access serde_qs hands out so sequences also accept a
+/// single value, ex: `tag=a` into a `Vec<String>`.
+///
+/// A key is only a sequence to serde_qs when it repeats. Sequences are requested as a newtype
+/// struct instead, which serde_qs gives a single value as a one item sequence.
+struct Lenient<T>(T);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Lenient<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        T::deserialize(Lenient(deserializer)).map(Lenient)
+    }
+}
+
+macro_rules! forward_deserialize {
+    ($($method: ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, D::Error> {
+                self.0.$method(Lenient(visitor))
+            }
+        )*
+    };
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for Lenient<D> {
+    type Error = D::Error;
+
+    fn deserialize_seq<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.0.deserialize_newtype_struct("", Lenient(visitor))
+    }
+
+    forward_deserialize!(
+        deserialize_any,
+        deserialize_bool,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_f32,
+        deserialize_f64,
+        deserialize_char,
+        deserialize_str,
+        deserialize_string,
+        deserialize_bytes,
+        deserialize_byte_buf,
+        deserialize_option,
+        deserialize_unit,
+        deserialize_map,
+        deserialize_identifier,
+        deserialize_ignored_any,
+    );
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.0.deserialize_unit_struct(name, Lenient(visitor))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.0.deserialize_newtype_struct(name, Lenient(visitor))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.0.deserialize_tuple(len, Lenient(visitor))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.0.deserialize_tuple_struct(name, len, Lenient(visitor))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.0.deserialize_struct(name, fields, Lenient(visitor))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.0.deserialize_enum(name, variants, Lenient(visitor))
+    }
+}
+
+macro_rules! forward_visit {
+    ($($method: ident: $ty: ty),* $(,)?) => {
+        $(
+            fn $method<E: de::Error>(self, value: $ty) -> std::result::Result<Self::Value, E> {
+                self.0.$method(value)
+            }
+        )*
+    };
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for Lenient<V> {
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.0.expecting(formatter)
+    }
+
+    forward_visit!(
+        visit_bool: bool,
+        visit_i8: i8,
+        visit_i16: i16,
+        visit_i32: i32,
+        visit_i64: i64,
+        visit_u8: u8,
+        visit_u16: u16,
+        visit_u32: u32,
+        visit_u64: u64,
+        visit_f32: f32,
+        visit_f64: f64,
+        visit_char: char,
+        visit_str: &str,
+        visit_borrowed_str: &'de str,
+        visit_string: String,
+        visit_bytes: &[u8],
+        visit_borrowed_bytes: &'de [u8],
+        visit_byte_buf: Vec<u8>,
+    );
+
+    fn visit_none<E: de::Error>(self) -> std::result::Result<Self::Value, E> {
+        self.0.visit_none()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<Self::Value, E> {
+        self.0.visit_unit()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Self::Value, D::Error> {
+        self.0.visit_some(Lenient(deserializer))
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Self::Value, D::Error> {
+        self.0.visit_newtype_struct(Lenient(deserializer))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> std::result::Result<Self::Value, A::Error> {
+        self.0.visit_seq(Lenient(seq))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> std::result::Result<Self::Value, A::Error> {
+        self.0.visit_map(Lenient(map))
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> std::result::Result<Self::Value, A::Error> {
+        self.0.visit_enum(data)
+    }
+}
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for Lenient<S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Self::Value, D::Error> {
+        self.0.deserialize(Lenient(deserializer))
+    }
+}
+
+impl<'de, A: SeqAccess<'de>> SeqAccess<'de> for Lenient<A> {
+    type Error = A::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> std::result::Result<Option<T::Value>, A::Error> {
+        self.0.next_element_seed(Lenient(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for Lenient<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> std::result::Result<Option<K::Value>, A::Error> {
+        self.0.next_key_seed(seed)
+    }
+
+    fn next_value_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> std::result::Result<T::Value, A::Error> {
+        self.0.next_value_seed(Lenient(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+}
+
+/// Deserialize a url encoded body
+pub(crate) fn from_form<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    let body = std::str::from_utf8(body)
+        .map_err(|err| Error::new(400, "Form body is not valid UTF-8").with_source(err))?;
+
+    // Non strict mode also accepts percent encoded brackets which browsers send
+    serde_qs::Config::new(5, false)
+        .deserialize_str::<Lenient<T>>(&index_repeated(body))
+        .map(|Lenient(value)| value)
+        .map_err(|err| Error::new(422, format!("Invalid form body: {}", err)).with_source(err))
+}
+
+impl RequestData {
+    fn form<T: DeserializeOwned>(&self) -> Result<Form<T>> {
+        match media_type(&self.3).as_deref() {
+            Some("application/x-www-form-urlencoded") => from_form(&self.2).map(Form),
+            Some(other) => Err(Error::new(
+                415,
+                format!(
+                    "Expected an application/x-www-form-urlencoded request body; found {}",
+                    other
+                ),
+            )),
+            None => Err(Error::new(
+                415,
+                "Expected an application/x-www-form-urlencoded request body",
+            )),
+        }
+    }
+}
+
+impl<T: DeserializeOwned> ToParam<Form<T>> for RequestData {
+    fn to_param(&mut self) -> Result<Form<T>> {
+        self.form()
+    }
+}
+
+impl<T: DeserializeOwned> ToParam<Option<Form<T>>> for RequestData {
+    fn to_param(&mut self) -> Result<Option<Form<T>>> {
+        Ok(self.form().ok())
+    }
+}
+
+impl<T: DeserializeOwned> ToParam<Result<Form<T>>> for RequestData {
+    fn to_param(&mut self) -> Result<Result<Form<T>>> {
+        Ok(self.form())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::{from_form, index_repeated};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Signup {
+        name: String,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        scores: Option<Vec<u8>>,
+    }
+
+    fn signup(body: &str) -> Signup {
+        from_form(body.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn single_keys_are_unchanged() {
+        assert_eq!(index_repeated("name=tela&age=1"), "name=tela&age=1");
+    }
+
+    #[test]
+    fn repeated_keys_are_indexed() {
+        assert_eq!(
+            index_repeated("tag=a&name=tela&tag=b&tag=c"),
+            "tag[0]=a&name=tela&tag[1]=b&tag[2]=c"
+        );
+    }
+
+    #[test]
+    fn bracketed_keys_are_unchanged() {
+        assert_eq!(index_repeated("tag[]=a&tag[]=b"), "tag[]=a&tag[]=b");
+        assert_eq!(
+            index_repeated("tag%5B%5D=a&tag%5b%5d=b"),
+            "tag%5B%5D=a&tag%5b%5d=b"
+        );
+    }
+
+    #[test]
+    fn empty_pairs_are_dropped() {
+        assert_eq!(index_repeated("a=1&&a=2&"), "a[0]=1&a[1]=2");
+        assert_eq!(index_repeated(""), "");
+    }
+
+    #[test]
+    fn keys_without_values() {
+        assert_eq!(index_repeated("a&a="), "a[0]&a[1]=");
+    }
+
+    #[test]
+    fn sequence_without_values() {
+        assert_eq!(signup("name=tela").tags, Vec::<String>::new());
+        assert_eq!(signup("name=tela").scores, None);
+    }
+
+    #[test]
+    fn sequence_with_one_value() {
+        assert_eq!(
+            signup("name=tela&tags=a&scores=1"),
+            Signup {
+                name: "tela".to_string(),
+                tags: vec!["a".to_string()],
+                scores: Some(vec![1]),
+            }
+        );
+    }
+
+    #[test]
+    fn sequence_with_many_values() {
+        assert_eq!(
+            signup("tags=a&name=tela&tags=b&scores=1&scores=2&tags=c"),
+            Signup {
+                name: "tela".to_string(),
+                tags: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+                scores: Some(vec![1, 2]),
+            }
+        );
+        assert_eq!(signup("name=tela&tags[]=a&tags[]=b").tags, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn nested_sequence_with_one_value() {
+        #[derive(Debug, Deserialize)]
+        struct Form {
+            user: Signup,
+        }
+
+        let form: Form = from_form(b"user[name]=tela&user[tags]=a").unwrap();
+        assert_eq!(form.user.tags, vec!["a"]);
+    }
+
+    #[test]
+    fn single_value_is_not_a_sequence() {
+        #[derive(Debug, Deserialize)]
+        struct Form {
+            #[allow(dead_code)]
+            name: String,
+        }
+
+        assert!(from_form::<Form>(b"name=a&name=b").is_err());
+        assert_eq!(
+            from_form::<Signup>(b"tags=a").unwrap_err().get_status(),
+            422
+        );
+    }
+}
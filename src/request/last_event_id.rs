@@ -0,0 +1,7 @@
+/// Value of the `Last-Event-ID` header a client sends when it reconnects to a server-sent event
+/// stream. Use it to resume the stream after the last event the client received.
+///
+/// Taking `LastEventId` directly responds with `400 Bad Request` when the header is missing; take
+/// `Option<LastEventId>` to handle first connections.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LastEventId(pub String);
@@ -1,16 +1,30 @@
+mod accept;
 mod body;
+mod body_stream;
+mod form;
+mod last_event_id;
+#[cfg(feature = "multipart")]
+mod multipart;
 mod query;
 mod request_data;
 
+pub use accept::Accept;
 pub use body::Body;
+pub use body_stream::{BodyStream, BodyUsage};
+pub use form::Form;
+pub use last_event_id::LastEventId;
+#[cfg(feature = "multipart")]
+pub use multipart::{Field, FromMultipart, Multipart, MultipartForm, TempFile};
 pub use query::Query;
 pub use request_data::{RequestData, ToParam};
 
-use bytes::Bytes;
-use http_body_util::Full;
-use std::fmt::Debug;
+pub(crate) use body::IntoBody;
+pub(crate) use body_stream::PendingBody;
+pub(crate) use query::IntoQuery;
 
-use crate::response::Result;
+use std::{fmt::Debug, ops::RangeInclusive};
+
+use crate::response::{Error, Response, Result};
 
 pub trait Endpoint: Sync + Send + Debug {
     fn methods(&self) -> Vec<hyper::Method>;
@@ -19,16 +33,48 @@ pub trait Endpoint: Sync + Send + Debug {
         &self,
         method: &hyper::Method,
         uri: &mut hyper::Uri,
+        headers: &hyper::HeaderMap,
         body: &mut Vec<u8>,
-    ) -> Result<hyper::Response<Full<Bytes>>>;
+        extensions: &mut hyper::http::Extensions,
+    ) -> Result<Response>;
+
+    /// How the request body is read before the endpoint is executed
+    fn body_usage(&self) -> BodyUsage {
+        BodyUsage::Buffered
+    }
+
+    /// Max size, in bytes, of the request body. Uses the server's limit when `None`.
+    fn body_limit(&self) -> Option<usize> {
+        None
+    }
 }
 
 pub trait Catch: Send + Sync + Debug {
+    /// Handle the error of a request. The body is whatever was read before the error occurred.
     fn execute(
         &self,
-        code: u16,
-        message: String,
-        reason: String,
-    ) -> Result<hyper::Response<Full<Bytes>>>;
-    fn code(&self) -> u16;
+        method: &hyper::Method,
+        uri: &hyper::Uri,
+        headers: &hyper::HeaderMap,
+        body: &[u8],
+        error: Error,
+    ) -> Result<Response>;
+
+    /// Inclusive ranges of the status codes that are handled. Empty handles every code.
+    fn codes(&self) -> Vec<RangeInclusive<u16>>;
+
+    /// Only handle errors of requests under this uri prefix, ex: `/api`
+    fn prefix(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Media type of the request body without parameters, ex: `application/json`
+pub(crate) fn media_type(headers: &hyper::HeaderMap) -> Option<String> {
+    headers
+        .get("Content-Type")
+        .and_then(|ct| ct.to_str().ok())
+        .and_then(|ct| ct.split(';').next())
+        .map(|ct| ct.trim().to_ascii_lowercase())
+        .filter(|ct| !ct.is_empty())
 }
@@ -0,0 +1,349 @@
+use std::{
+    io::Write,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use bytes::Bytes;
+use hyper::{
+    header::{HeaderName, HeaderValue},
+    HeaderMap,
+};
+use tempfile::NamedTempFile;
+
+use crate::response::{Error, Result};
+
+use super::{body_stream::block_on, BodyStream, RequestData, ToParam};
+
+pub use tela_macros::FromMultipart;
+
+fn multipart_error(err: multer::Error) -> Error {
+    match err {
+        // Errors from reading the body keep their status code through the multipart parser
+        multer::Error::StreamReadFailed(err) => match err.downcast::<Error>() {
+            Ok(err) => *err,
+            Err(err) => Error::new(400, format!("Failed to read request body: {}", err)),
+        },
+        multer::Error::FieldSizeExceeded { limit, field_name } => Error::new(
+            413,
+            format!(
+                "Multipart field {} exceeds {} bytes",
+                field_name.unwrap_or_default(),
+                limit
+            ),
+        ),
+        multer::Error::StreamSizeExceeded { limit } => {
+            Error::new(413, format!("Multipart body exceeds {} bytes", limit))
+        }
+        err => Error::new(400, format!("Invalid multipart body: {}", err)).with_source(err),
+    }
+}
+
+/// Extractor for `multipart/form-data` bodies that reads the parts one at a time
+///
+/// Parts are streamed from the request so large file uploads are never fully in memory. Requests
+/// with another content type are rejected with `415 Unsupported Media Type` and fields larger than
+/// their limit with `413 Payload Too Large`.
+///
+/// Requires the `multipart` feature.
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use tela::{prelude::*, request::Multipart};
+///
+/// #[post("/upload", limit = "100MB")]
+/// fn upload(multipart: Multipart) -> Result<String> {
+///     let mut multipart = multipart.field_limit(50 * 1024 * 1024);
+///     let mut saved = Vec::new();
+///     while let Some(field) = multipart.next_field()? {
+///         // Never trust the client's path; only keep the file name
+///         let name = field
+///             .file_name()
+///             .and_then(|name| Path::new(name).file_name())
+///             .map(|name| name.to_string_lossy().to_string());
+///         if let Some(name) = name {
+///             field.temp_file()?.persist(Path::new("uploads").join(&name))?;
+///             saved.push(name);
+///         }
+///     }
+///     Ok(format!("Saved {}", saved.join(", ")))
+/// }
+/// ```
+pub struct Multipart {
+    inner: multer::Multipart<'static>,
+    field_limit: Option<usize>,
+    limit: Option<usize>,
+    read: Arc<AtomicUsize>,
+}
+
+impl Multipart {
+    /// Max size, in bytes, of each field
+    pub fn field_limit(mut self, bytes: usize) -> Self {
+        self.field_limit = Some(bytes);
+        self
+    }
+
+    /// Max size, in bytes, of all fields combined. The body limit of the endpoint still applies.
+    pub fn limit(mut self, bytes: usize) -> Self {
+        self.limit = Some(bytes);
+        self
+    }
+
+    /// Get the next field. Returns `None` once all fields are read.
+    ///
+    /// Any data left in the previous field is skipped.
+    pub fn next_field(&mut self) -> Result<Option<Field>> {
+        match block_on(self.inner.next_field())? {
+            Ok(Some(inner)) => Ok(Some(Field {
+                headers: headers(&inner),
+                inner,
+                limit: self.field_limit,
+                total: self.limit,
+                size: 0,
+                read: self.read.clone(),
+            })),
+            Ok(None) => Ok(None),
+            Err(err) => Err(multipart_error(err)),
+        }
+    }
+}
+
+/// Copy the headers of a part, which multer gives with an older version of `http`
+fn headers(field: &multer::Field<'static>) -> HeaderMap {
+    field
+        .headers()
+        .iter()
+        .filter_map(|(key, value)| {
+            Some((
+                HeaderName::from_bytes(key.as_str().as_bytes()).ok()?,
+                HeaderValue::from_bytes(value.as_bytes()).ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// Single part of a `multipart/form-data` body
+pub struct Field {
+    inner: multer::Field<'static>,
+    headers: HeaderMap,
+    limit: Option<usize>,
+    total: Option<usize>,
+    size: usize,
+    read: Arc<AtomicUsize>,
+}
+
+impl Field {
+    /// Name of the form field
+    pub fn name(&self) -> Option<&str> {
+        self.inner.name()
+    }
+
+    /// Name of the uploaded file if the field is a file
+    pub fn file_name(&self) -> Option<&str> {
+        self.inner.file_name()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.inner.content_type().map(|mime| mime.as_ref())
+    }
+
+    /// Headers of the part
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Max size, in bytes, of this field. Overrides the multipart's field limit.
+    pub fn limit(mut self, bytes: usize) -> Self {
+        self.limit = Some(bytes);
+        self
+    }
+
+    /// Get the next chunk of the field's data. Returns `None` once the field is read.
+    pub fn chunk(&mut self) -> Option<Result<Bytes>> {
+        let chunk = match block_on(self.inner.chunk()) {
+            Ok(Ok(Some(chunk))) => chunk,
+            Ok(Ok(None)) => return None,
+            Ok(Err(err)) => return Some(Err(multipart_error(err))),
+            Err(err) => return Some(Err(err)),
+        };
+
+        self.size += chunk.len();
+        if let Some(limit) = self.limit {
+            if self.size > limit {
+                return Some(Err(Error::new(
+                    413,
+                    format!(
+                        "Multipart field {} exceeds {} bytes",
+                        self.name().unwrap_or_default(),
+                        limit
+                    ),
+                )));
+            }
+        }
+
+        let read = self.read.fetch_add(chunk.len(), Ordering::Relaxed) + chunk.len();
+        if let Some(total) = self.total {
+            if read > total {
+                return Some(Err(Error::new(
+                    413,
+                    format!("Multipart body exceeds {} bytes", total),
+                )));
+            }
+        }
+
+        Some(Ok(chunk))
+    }
+
+    /// Read all of the field's data into memory
+    pub fn bytes(mut self) -> Result<Bytes> {
+        let mut data = Vec::new();
+        while let Some(chunk) = self.chunk() {
+            data.extend_from_slice(&chunk?);
+        }
+        Ok(Bytes::from(data))
+    }
+
+    /// Read all of the field's data as text
+    pub fn text(self) -> Result<String> {
+        let name = self.name().unwrap_or_default().to_string();
+        String::from_utf8(self.bytes()?.to_vec()).map_err(|err| {
+            Error::new(400, format!("Multipart field {} is not valid UTF-8", name)).with_source(err)
+        })
+    }
+
+    /// Write the field's data to a temporary file
+    pub fn temp_file(mut self) -> Result<TempFile> {
+        let error =
+            |err: std::io::Error| Error::new(500, "Failed to write upload").with_source(err);
+
+        let mut file = NamedTempFile::new().map_err(error)?;
+        while let Some(chunk) = self.chunk() {
+            file.write_all(&chunk?).map_err(error)?;
+        }
+        file.flush().map_err(error)?;
+
+        Ok(TempFile {
+            file_name: self.file_name().map(|name| name.to_string()),
+            content_type: self.content_type().map(|ct| ct.to_string()),
+            size: self.size,
+            file,
+        })
+    }
+}
+
+/// Uploaded file stored in a temporary file. The file is deleted when this is dropped unless it
+/// is persisted.
+#[derive(Debug)]
+pub struct TempFile {
+    file: NamedTempFile,
+    file_name: Option<String>,
+    content_type: Option<String>,
+    size: usize,
+}
+
+impl TempFile {
+    /// Path of the temporary file
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+
+    /// Name of the file given by the client
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Size of the file in bytes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Move the file to a permanent location
+    pub fn persist<P: AsRef<Path>>(self, path: P) -> Result<std::fs::File> {
+        self.file
+            .persist(path)
+            .map_err(|err| Error::new(500, "Failed to save upload").with_source(err.error))
+    }
+}
+
+/// Build a value from the fields of a `multipart/form-data` body
+///
+/// Usually derived with `#[derive(FromMultipart)]`. Text fields are parsed with `FromStr` and
+/// [`TempFile`] fields are written to temporary files. `Option` fields may be missing and `Vec`
+/// fields collect every field with the same name. Limits are set with
+/// `#[multipart(limit = "10MB")]` on the struct for all fields combined, or on a field for that
+/// field alone.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, request::{FromMultipart, MultipartForm, TempFile}};
+///
+/// #[derive(FromMultipart)]
+/// #[multipart(limit = "20MB")]
+/// struct Profile {
+///     name: String,
+///     age: Option<u8>,
+///     #[multipart(limit = "5MB")]
+///     avatar: TempFile,
+/// }
+///
+/// #[post("/profile", limit = "20MB")]
+/// fn profile(MultipartForm(form): MultipartForm<Profile>) -> Result<String> {
+///     form.avatar.persist(format!("avatars/{}", form.name))?;
+///     Ok(format!("Saved {}", form.name))
+/// }
+/// ```
+pub trait FromMultipart: Sized {
+    fn from_multipart(multipart: Multipart) -> Result<Self>;
+}
+
+/// Extractor that builds `T` from a `multipart/form-data` body
+#[derive(Debug)]
+pub struct MultipartForm<T: FromMultipart>(pub T);
+
+impl RequestData {
+    fn multipart(&mut self) -> Result<Multipart> {
+        let boundary = self
+            .3
+            .get("Content-Type")
+            .and_then(|ct| ct.to_str().ok())
+            .and_then(|ct| multer::parse_boundary(ct).ok())
+            .ok_or_else(|| Error::new(415, "Expected a multipart/form-data request body"))?;
+
+        let stream: BodyStream = self.to_param()?;
+
+        Ok(Multipart {
+            inner: multer::Multipart::new(stream, boundary),
+            field_limit: None,
+            limit: None,
+            read: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+}
+
+impl ToParam<Multipart> for RequestData {
+    fn to_param(&mut self) -> Result<Multipart> {
+        self.multipart()
+    }
+}
+
+impl<T: FromMultipart> ToParam<MultipartForm<T>> for RequestData {
+    fn to_param(&mut self) -> Result<MultipartForm<T>> {
+        T::from_multipart(self.multipart()?).map(MultipartForm)
+    }
+}
+
+impl<T: FromMultipart> ToParam<Result<MultipartForm<T>>> for RequestData {
+    fn to_param(&mut self) -> Result<Result<MultipartForm<T>>> {
+        Ok(self
+            .multipart()
+            .and_then(|multipart| T::from_multipart(multipart).map(MultipartForm)))
+    }
+}
@@ -1,4 +1,4 @@
-use crate::response::Result;
+use crate::response::{Error, Result};
 use serde::{Deserialize, Serialize};
 
 pub trait IntoQuery {
@@ -26,7 +26,7 @@ impl<'a, T: Deserialize<'a> + Default + Serialize> IntoQuery for T {
             Ok(result) => Ok(Query(result)),
             Err(_) => match serde_plain::from_str::<T>(Box::leak(query.clone().into_boxed_str())) {
                 Ok(result) => Ok(Query(result)),
-                Err(_) => Err((
+                Err(_) => Err(Error::new(
                     500,
                     format!(
                         "Failed to parse query from request; expected <span class=path>?{}</span>",
@@ -1,17 +1,58 @@
-use crate::response::Result;
+use bytes::Bytes;
 
-use super::{body::IntoBody, query::IntoQuery, Body, Query};
+use hyper::{HeaderMap, Method, Uri};
+
+use crate::response::{Error, Result};
+
+use super::{
+    body::IntoBody, media_type, query::IntoQuery, Body, BodyStream, LastEventId, PendingBody, Query,
+};
 
 pub trait ToParam<T> {
     fn to_param(&mut self) -> Result<T>;
 }
-pub struct RequestData(pub hyper::Uri, pub hyper::Method, pub Vec<u8>);
+pub struct RequestData(
+    pub hyper::Uri,
+    pub hyper::Method,
+    pub Vec<u8>,
+    pub hyper::HeaderMap,
+    pub hyper::http::Extensions,
+    /// Error being handled by a catch handler
+    pub Option<Error>,
+);
+
+impl ToParam<Uri> for RequestData {
+    fn to_param(&mut self) -> Result<Uri> {
+        Ok(self.0.clone())
+    }
+}
+
+impl ToParam<Method> for RequestData {
+    fn to_param(&mut self) -> Result<Method> {
+        Ok(self.1.clone())
+    }
+}
+
+impl ToParam<HeaderMap> for RequestData {
+    fn to_param(&mut self) -> Result<HeaderMap> {
+        Ok(self.3.clone())
+    }
+}
+
+/// The error is only available in catch handlers
+impl ToParam<Error> for RequestData {
+    fn to_param(&mut self) -> Result<Error> {
+        self.5
+            .take()
+            .ok_or_else(|| Error::new(500, "There is no error to catch"))
+    }
+}
 
 impl<T: IntoQuery> ToParam<Query<T>> for RequestData {
     fn to_param(&mut self) -> Result<Query<T>> {
         match self.0.query() {
             Some(query) => T::into_query(query),
-            _ => Err((500, "No query to parse".to_string())),
+            _ => Err(Error::new(500, "No query to parse")),
         }
     }
 }
@@ -29,28 +70,74 @@ impl<T: IntoQuery> ToParam<Result<Query<T>>> for RequestData {
     fn to_param(&mut self) -> Result<Result<Query<T>>> {
         match self.0.query() {
             Some(query) => Ok(T::into_query(query)),
-            _ => Ok(Err((500, "No query to parse".to_string()))),
+            _ => Ok(Err(Error::new(500, "No query to parse"))),
         }
     }
 }
 
+impl RequestData {
+    fn body<T: IntoBody>(&self) -> Result<Body<T>> {
+        T::into_body(&self.2, media_type(&self.3).as_deref())
+    }
+}
+
 impl<T: IntoBody> ToParam<Body<T>> for RequestData {
     fn to_param(&mut self) -> Result<Body<T>> {
-        let body = std::str::from_utf8(&self.2[..]).unwrap();
-        T::into_body(body)
+        self.body()
     }
 }
 
 impl<T: IntoBody> ToParam<Option<Body<T>>> for RequestData {
     fn to_param(&mut self) -> Result<Option<Body<T>>> {
-        let body = std::str::from_utf8(&self.2[..]).unwrap();
-        Ok(T::into_body(body).ok())
+        Ok(self.body().ok())
     }
 }
 
 impl<T: IntoBody> ToParam<Result<Body<T>>> for RequestData {
     fn to_param(&mut self) -> Result<Result<Body<T>>> {
-        let body = std::str::from_utf8(&self.2[..]).unwrap();
-        Ok(T::into_body(body))
+        Ok(self.body())
+    }
+}
+
+impl ToParam<Bytes> for RequestData {
+    fn to_param(&mut self) -> Result<Bytes> {
+        Ok(Bytes::from(std::mem::take(&mut self.2)))
+    }
+}
+
+impl ToParam<Vec<u8>> for RequestData {
+    fn to_param(&mut self) -> Result<Vec<u8>> {
+        Ok(std::mem::take(&mut self.2))
+    }
+}
+
+impl RequestData {
+    fn last_event_id(&self) -> Option<LastEventId> {
+        self.3
+            .get("Last-Event-ID")
+            .and_then(|id| id.to_str().ok())
+            .map(|id| LastEventId(id.to_string()))
+    }
+}
+
+impl ToParam<LastEventId> for RequestData {
+    fn to_param(&mut self) -> Result<LastEventId> {
+        self.last_event_id()
+            .ok_or_else(|| Error::new(400, "Missing Last-Event-ID header"))
+    }
+}
+
+impl ToParam<Option<LastEventId>> for RequestData {
+    fn to_param(&mut self) -> Result<Option<LastEventId>> {
+        Ok(self.last_event_id())
+    }
+}
+
+impl ToParam<BodyStream> for RequestData {
+    fn to_param(&mut self) -> Result<BodyStream> {
+        self.4
+            .remove::<PendingBody>()
+            .and_then(|pending| pending.take())
+            .ok_or_else(|| Error::new(500, "Request body was already read"))
     }
 }
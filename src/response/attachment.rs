@@ -0,0 +1,215 @@
+use hyper::{
+    header::{HeaderValue, CONTENT_DISPOSITION, CONTENT_TYPE},
+    HeaderMap, Method, Uri,
+};
+
+use super::{Response, Result, ToErrorResponse, ToResponse};
+
+/// How the client should present the content of a response
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Disposition {
+    /// Display the content in the browser
+    Inline,
+    /// Download the content as a file
+    Attachment,
+}
+
+/// Characters that can be sent as is in an RFC 5987 `filename*` parameter
+fn is_attr_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte)
+}
+
+/// Value of a `Content-Disposition` header, ex: `attachment; filename="report.pdf"`
+///
+/// Names with characters that can't be sent in a quoted string get an ascii `filename` fallback
+/// along with the full name percent encoded in `filename*` as described in RFC 6266 and RFC 5987.
+pub fn content_disposition(disposition: Disposition, filename: &str) -> String {
+    let kind = match disposition {
+        Disposition::Inline => "inline",
+        Disposition::Attachment => "attachment",
+    };
+
+    let fallback = filename
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if c != '"' && c != '\\' && c != '%' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+
+    if fallback == filename {
+        return format!("{}; filename=\"{}\"", kind, filename);
+    }
+
+    let encoded = filename
+        .bytes()
+        .map(|byte| match is_attr_char(byte) {
+            true => (byte as char).to_string(),
+            false => format!("%{:02X}", byte),
+        })
+        .collect::<String>();
+    format!(
+        "{}; filename=\"{}\"; filename*=UTF-8''{}",
+        kind, fallback, encoded
+    )
+}
+
+/// Response that the client saves as a file with the given name
+///
+/// Any response can be wrapped, ex: `Vec<u8>`, `Bytes`, `String`, or [`File`][super::File]. When
+/// the wrapped response is sent as `application/octet-stream` or without a content type, the
+/// content type is guessed from the filename.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, response::{Attachment, File}};
+///
+/// #[get("/invoice")]
+/// fn invoice() -> Attachment<Vec<u8>> {
+///     Attachment::new("facture-été.pdf", std::fs::read("invoice.pdf").unwrap_or_default())
+/// }
+///
+/// #[get("/manual")]
+/// fn manual() -> Attachment<File<&'static str>> {
+///     // Show the pdf in the browser instead of downloading it
+///     File("docs/manual.pdf").inline()
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Attachment<T> {
+    inner: T,
+    filename: String,
+    disposition: Disposition,
+    content_type: Option<String>,
+}
+
+impl<T> Attachment<T> {
+    pub fn new<N: Into<String>>(filename: N, inner: T) -> Self {
+        Attachment {
+            inner,
+            filename: filename.into(),
+            disposition: Disposition::Attachment,
+            content_type: None,
+        }
+    }
+
+    /// Display the content in the browser while keeping the filename for when it is saved
+    pub fn inline(mut self) -> Self {
+        self.disposition = Disposition::Inline;
+        self
+    }
+
+    pub fn disposition(mut self, disposition: Disposition) -> Self {
+        self.disposition = disposition;
+        self
+    }
+
+    /// Content type to send instead of the one guessed from the filename
+    pub fn content_type<C: Into<String>>(mut self, content_type: C) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    fn apply(
+        filename: String,
+        disposition: Disposition,
+        content_type: Option<String>,
+        mut response: Response,
+    ) -> Response {
+        let generic = match response.headers().get(CONTENT_TYPE) {
+            Some(current) => current == "application/octet-stream",
+            None => true,
+        };
+        let content_type = content_type.or_else(|| match generic {
+            true => mime_guess::from_path(&filename)
+                .first()
+                .map(|mime| mime.to_string()),
+            false => None,
+        });
+        if let Some(value) = content_type.and_then(|ct| HeaderValue::from_str(&ct).ok()) {
+            response.headers_mut().insert(CONTENT_TYPE, value);
+        }
+
+        if let Ok(value) = HeaderValue::from_str(&content_disposition(disposition, &filename)) {
+            response.headers_mut().insert(CONTENT_DISPOSITION, value);
+        }
+        response
+    }
+}
+
+impl<T: ToResponse> ToResponse for Attachment<T> {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: String,
+    ) -> Result<Response> {
+        let response = self.inner.to_response(method, uri, headers, body)?;
+        Ok(Attachment::<T>::apply(
+            self.filename,
+            self.disposition,
+            self.content_type,
+            response,
+        ))
+    }
+}
+
+impl<T: ToErrorResponse> ToErrorResponse for Attachment<T> {
+    fn to_error_response(self, code: u16, reason: String) -> Result<Response> {
+        let response = self.inner.to_error_response(code, reason)?;
+        Ok(Attachment::<T>::apply(
+            self.filename,
+            self.disposition,
+            self.content_type,
+            response,
+        ))
+    }
+    fn to_error_response_with(
+        self,
+        code: u16,
+        reason: String,
+        headers: &HeaderMap,
+    ) -> Result<Response> {
+        let response = self.inner.to_error_response_with(code, reason, headers)?;
+        Ok(Attachment::<T>::apply(
+            self.filename,
+            self.disposition,
+            self.content_type,
+            response,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{content_disposition, Disposition};
+
+    #[test]
+    fn ascii_names() {
+        assert_eq!(
+            content_disposition(Disposition::Attachment, "report.pdf"),
+            "attachment; filename=\"report.pdf\""
+        );
+        assert_eq!(
+            content_disposition(Disposition::Inline, "my report.pdf"),
+            "inline; filename=\"my report.pdf\""
+        );
+    }
+
+    #[test]
+    fn non_ascii_names_are_encoded() {
+        assert_eq!(
+            content_disposition(Disposition::Attachment, "facture-été.pdf"),
+            "attachment; filename=\"facture-_t_.pdf\"; filename*=UTF-8''facture-%C3%A9t%C3%A9.pdf"
+        );
+    }
+
+    #[test]
+    fn quotes_and_separators_are_encoded() {
+        assert_eq!(
+            content_disposition(Disposition::Attachment, "a \"b\"\\c%.txt"),
+            "attachment; filename=\"a _b__c_.txt\"; filename*=UTF-8''a%20%22b%22%5Cc%25.txt"
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use std::{
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
+
+/// When a cookie is sent with cross site requests
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl Display for SameSite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SameSite::Strict => write!(f, "Strict"),
+            SameSite::Lax => write!(f, "Lax"),
+            SameSite::None => write!(f, "None"),
+        }
+    }
+}
+
+/// Cookie sent to the client with a `Set-Cookie` header
+///
+/// Names must be RFC 6265 tokens. Characters of the value that a cookie can't hold, like spaces,
+/// quotes, and `;`, are percent encoded.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use tela::{prelude::*, response::{Cookie, SameSite, HTML}};
+///
+/// #[post("/login")]
+/// fn login() -> impl ToResponse {
+///     HTML("<h1>Welcome</h1>").cookie(
+///         Cookie::new("session", "abc123")
+///             .path("/")
+///             .max_age(Duration::from_secs(60 * 60))
+///             .http_only(true)
+///             .secure(true)
+///             .same_site(SameSite::Lax),
+///     )
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    domain: Option<String>,
+    max_age: Option<Duration>,
+    expires: Option<SystemTime>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+}
+
+impl Cookie {
+    pub fn new<N: Into<String>, V: Into<String>>(name: N, value: V) -> Self {
+        Cookie {
+            name: name.into(),
+            value: value.into(),
+            path: None,
+            domain: None,
+            max_age: None,
+            expires: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        }
+    }
+
+    /// Cookie that removes the cookie with the same name from the client
+    pub fn removal<N: Into<String>>(name: N) -> Self {
+        Cookie::new(name, "")
+            .max_age(Duration::ZERO)
+            .expires(SystemTime::UNIX_EPOCH)
+    }
+
+    pub fn path<T: Into<String>>(mut self, path: T) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn domain<T: Into<String>>(mut self, domain: T) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    /// How long until the cookie expires
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// When the cookie expires
+    pub fn expires(mut self, expires: SystemTime) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    /// Only send the cookie over https
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Hide the cookie from javascript
+    pub fn http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Check that the cookie can be sent without changing the meaning of the header
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() || !self.name.bytes().all(is_token) {
+            return Err(format!("Invalid cookie name {:?}", self.name));
+        }
+        for (attribute, value) in [("path", &self.path), ("domain", &self.domain)] {
+            if let Some(value) = value {
+                if value.bytes().any(|b| b == b';' || b.is_ascii_control()) {
+                    return Err(format!("Invalid cookie {} {:?}", attribute, value));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `tchar` of RFC 7230, the characters of a cookie name
+fn is_token(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte)
+}
+
+/// `cookie-octet` of RFC 6265, the characters of a cookie value
+fn is_cookie_octet(byte: u8) -> bool {
+    matches!(byte, 0x21 | 0x23..=0x2B | 0x2D..=0x3A | 0x3C..=0x5B | 0x5D..=0x7E)
+}
+
+impl Display for Cookie {
+    /// Value of the `Set-Cookie` header
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}=", self.name)?;
+        for byte in self.value.bytes() {
+            match is_cookie_octet(byte) {
+                true => write!(f, "{}", byte as char)?,
+                false => write!(f, "%{:02X}", byte)?,
+            }
+        }
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", path)?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+        if let Some(max_age) = &self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if let Some(expires) = &self.expires {
+            write!(f, "; Expires={}", httpdate::fmt_http_date(*expires))?;
+        }
+        if self.secure {
+            write!(f, "; Secure")?;
+        }
+        if self.http_only {
+            write!(f, "; HttpOnly")?;
+        }
+        if let Some(same_site) = &self.same_site {
+            write!(f, "; SameSite={}", same_site)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Cookie, SameSite};
+
+    #[test]
+    fn attributes() {
+        let cookie = Cookie::new("session", "abc123")
+            .path("/")
+            .max_age(Duration::from_secs(60))
+            .http_only(true)
+            .same_site(SameSite::Lax);
+        assert_eq!(
+            cookie.to_string(),
+            "session=abc123; Path=/; Max-Age=60; HttpOnly; SameSite=Lax"
+        );
+        assert!(cookie.validate().is_ok());
+    }
+
+    #[test]
+    fn value_is_encoded() {
+        assert_eq!(
+            Cookie::new("a", "x; Path=/admin\r\n").to_string(),
+            "a=x%3B%20Path=/admin%0D%0A"
+        );
+        assert_eq!(
+            Cookie::new("a", "\"q\",\\é").to_string(),
+            "a=%22q%22%2C%5C%C3%A9"
+        );
+        assert_eq!(Cookie::new("a", "b%20c").to_string(), "a=b%20c");
+    }
+
+    #[test]
+    fn invalid_names() {
+        for name in ["", "a b", "a;b", "a=b", "a\r\nb", "é"] {
+            assert!(Cookie::new(name, "1").validate().is_err(), "{:?}", name);
+        }
+        assert!(Cookie::new("__Host-id", "1").validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_attributes() {
+        assert!(Cookie::new("a", "1").path("/; Secure").validate().is_err());
+        assert!(Cookie::new("a", "1")
+            .domain("example.com\n")
+            .validate()
+            .is_err());
+    }
+}
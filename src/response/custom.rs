@@ -0,0 +1,345 @@
+use hyper::{
+    header::{HeaderName, HeaderValue, SET_COOKIE},
+    HeaderMap, Method, Uri,
+};
+
+use crate::StatusCode;
+
+use super::{Cookie, Error, Response, Result, ToErrorResponse, ToResponse};
+
+/// Headers added to a response, ex: `(201, Headers::from([("X-Id", "1")]), JSON(user))`
+///
+/// Invalid headers make the response fail with a 500 error.
+#[derive(Debug, Clone, Default)]
+pub struct Headers {
+    map: HeaderMap,
+    /// Reason the first invalid header was rejected
+    invalid: Option<String>,
+}
+
+impl Headers {
+    pub fn new() -> Self {
+        Headers::default()
+    }
+
+    pub fn header<K: TryInto<HeaderName>, V: TryInto<HeaderValue>>(
+        mut self,
+        key: K,
+        value: V,
+    ) -> Self {
+        let key = match key.try_into() {
+            Ok(key) => key,
+            Err(_) => return self.reject("Invalid header name".to_string()),
+        };
+        match value.try_into() {
+            Ok(value) => {
+                self.map.append(key, value);
+                self
+            }
+            Err(_) => self.reject(format!("Invalid value for header {}", key)),
+        }
+    }
+
+    /// Add a `Set-Cookie` header
+    pub fn cookie(self, cookie: Cookie) -> Self {
+        match cookie.validate() {
+            Ok(()) => self.header(SET_COOKIE, cookie.to_string()),
+            Err(reason) => self.reject(reason),
+        }
+    }
+
+    /// Merge in the headers, and any invalid header, of another set of headers
+    fn extend(mut self, headers: Headers) -> Self {
+        for (key, value) in headers.map.iter() {
+            self.map.append(key, value.clone());
+        }
+        match headers.invalid {
+            Some(reason) => self.reject(reason),
+            None => self,
+        }
+    }
+
+    fn reject(mut self, reason: String) -> Self {
+        self.invalid.get_or_insert(reason);
+        self
+    }
+
+    /// Replace the response's values for each header. `Set-Cookie` values are kept.
+    fn apply(self, response: &mut Response) -> Result<()> {
+        if let Some(reason) = self.invalid {
+            return Err(Error::new(500, reason));
+        }
+        for key in self.map.keys() {
+            if key != SET_COOKIE {
+                response.headers_mut().remove(key);
+            }
+        }
+        for (key, value) in self.map.iter() {
+            response.headers_mut().append(key, value.clone());
+        }
+        Ok(())
+    }
+}
+
+impl<K: TryInto<HeaderName>, V: TryInto<HeaderValue>, const SIZE: usize> From<[(K, V); SIZE]>
+    for Headers
+{
+    fn from(headers: [(K, V); SIZE]) -> Self {
+        headers
+            .into_iter()
+            .fold(Headers::new(), |headers, (key, value)| {
+                headers.header(key, value)
+            })
+    }
+}
+
+impl From<HeaderMap> for Headers {
+    fn from(headers: HeaderMap) -> Self {
+        Headers {
+            map: headers,
+            invalid: None,
+        }
+    }
+}
+
+/// Response with a custom status, headers, or cookies
+///
+/// Created with the methods of [`ResponseExt`] on any [`ToResponse`] type. The wrapped response is
+/// built as usual and then changed in place.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, response::{Cookie, HTML}, StatusCode};
+///
+/// #[post("/posts")]
+/// fn create() -> impl ToResponse {
+///     HTML("<h1>Created</h1>")
+///         .status(StatusCode::Created)
+///         .header("Location", "/posts/1")
+///         .cookie(Cookie::new("last_post", "1"))
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Custom<T> {
+    inner: T,
+    status: Option<u16>,
+    headers: Headers,
+}
+
+impl<T> Custom<T> {
+    pub fn new(inner: T) -> Self {
+        Custom {
+            inner,
+            status: None,
+            headers: Headers::new(),
+        }
+    }
+
+    pub fn status<S: Into<u16>>(mut self, status: S) -> Self {
+        self.status = Some(status.into());
+        self
+    }
+
+    /// Set a header, replacing the value set by the wrapped response
+    pub fn header<K: TryInto<HeaderName>, V: TryInto<HeaderValue>>(
+        mut self,
+        key: K,
+        value: V,
+    ) -> Self {
+        self.headers = self.headers.header(key, value);
+        self
+    }
+
+    pub fn headers<H: Into<Headers>>(mut self, headers: H) -> Self {
+        self.headers = self.headers.extend(headers.into());
+        self
+    }
+
+    /// Add a `Set-Cookie` header. Cookies with an invalid name or attribute fail the response.
+    pub fn cookie(mut self, cookie: Cookie) -> Self {
+        self.headers = self.headers.cookie(cookie);
+        self
+    }
+
+    /// Split into the wrapped response and the changes to make to it
+    fn into_parts(self) -> (T, Parts) {
+        (
+            self.inner,
+            Parts {
+                status: self.status,
+                headers: self.headers,
+            },
+        )
+    }
+}
+
+/// Status and headers set on a response after it is built
+struct Parts {
+    status: Option<u16>,
+    headers: Headers,
+}
+
+impl Parts {
+    fn apply(self, mut response: Response) -> Result<Response> {
+        if let Some(status) = self.status {
+            *response.status_mut() = hyper::StatusCode::from_u16(status).map_err(|err| {
+                Error::new(500, format!("Invalid status code {}", status)).with_source(err)
+            })?;
+        }
+        self.headers.apply(&mut response)?;
+        Ok(response)
+    }
+}
+
+impl<T: ToResponse> ToResponse for Custom<T> {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: String,
+    ) -> Result<Response> {
+        let (inner, parts) = self.into_parts();
+        parts.apply(inner.to_response(method, uri, headers, body)?)
+    }
+}
+
+impl<T: ToErrorResponse> ToErrorResponse for Custom<T> {
+    fn to_error_response(self, code: u16, reason: String) -> Result<Response> {
+        let (inner, parts) = self.into_parts();
+        parts.apply(inner.to_error_response(code, reason)?)
+    }
+
+    fn to_error_response_with(
+        self,
+        code: u16,
+        reason: String,
+        headers: &HeaderMap,
+    ) -> Result<Response> {
+        let (inner, parts) = self.into_parts();
+        parts.apply(inner.to_error_response_with(code, reason, headers)?)
+    }
+}
+
+/// Change the status, headers, or cookies of any response
+///
+/// See [`Custom`].
+pub trait ResponseExt: Sized {
+    fn status<S: Into<u16>>(self, status: S) -> Custom<Self> {
+        Custom::new(self).status(status)
+    }
+
+    fn header<K: TryInto<HeaderName>, V: TryInto<HeaderValue>>(
+        self,
+        key: K,
+        value: V,
+    ) -> Custom<Self> {
+        Custom::new(self).header(key, value)
+    }
+
+    fn headers<H: Into<Headers>>(self, headers: H) -> Custom<Self> {
+        Custom::new(self).headers(headers)
+    }
+
+    fn cookie(self, cookie: Cookie) -> Custom<Self> {
+        Custom::new(self).cookie(cookie)
+    }
+}
+
+impl<T: ToResponse> ResponseExt for T {}
+
+impl<T: ToResponse> ToResponse for (u16, Headers, T) {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: String,
+    ) -> Result<Response> {
+        let (status, extra, inner) = self;
+        Custom::new(inner)
+            .status(status)
+            .headers(extra)
+            .to_response(method, uri, headers, body)
+    }
+}
+
+impl<T: ToResponse> ToResponse for (StatusCode, Headers, T) {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: String,
+    ) -> Result<Response> {
+        let (status, extra, inner) = self;
+        (status as u16, extra, inner).to_response(method, uri, headers, body)
+    }
+}
+
+impl<T: ToResponse> ToResponse for (Headers, T) {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: String,
+    ) -> Result<Response> {
+        let (extra, inner) = self;
+        Custom::new(inner)
+            .headers(extra)
+            .to_response(method, uri, headers, body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{HeaderMap, Method, Uri};
+
+    use super::{Headers, ResponseExt, ToResponse};
+    use crate::response::{Cookie, Response, Result};
+
+    fn build<T: ToResponse>(response: T) -> Result<Response> {
+        response.to_response(
+            &Method::GET,
+            &Uri::from_static("/"),
+            &HeaderMap::new(),
+            String::new(),
+        )
+    }
+
+    #[test]
+    fn headers_and_cookies_are_set() {
+        let response = build(
+            "ok".header("X-Id", "1")
+                .cookie(Cookie::new("a", "1"))
+                .cookie(Cookie::new("b", "2 3")),
+        )
+        .unwrap();
+        assert_eq!(response.headers().get("X-Id").unwrap(), "1");
+        let cookies: Vec<_> = response.headers().get_all("Set-Cookie").iter().collect();
+        assert_eq!(cookies, ["a=1", "b=2%203"]);
+    }
+
+    #[test]
+    fn invalid_headers_are_errors() {
+        let error = build("ok".header("X-Id", "1\r\nSet-Cookie: a=b")).unwrap_err();
+        assert_eq!(error.get_status(), 500);
+        assert_eq!(error.reason(), "Invalid value for header x-id");
+
+        let error = build("ok".header("X Id", "1")).unwrap_err();
+        assert_eq!(error.get_status(), 500);
+
+        let error = build((Headers::from([("X-Id", "\n")]), "ok")).unwrap_err();
+        assert_eq!(error.get_status(), 500);
+    }
+
+    #[test]
+    fn invalid_cookies_are_errors() {
+        let error = build("ok".cookie(Cookie::new("a;b", "1"))).unwrap_err();
+        assert_eq!(error.get_status(), 500);
+
+        let error = build("ok".cookie(Cookie::new("a", "1").path("/\r\nX-Admin: 1"))).unwrap_err();
+        assert_eq!(error.get_status(), 500);
+    }
+}
@@ -0,0 +1,344 @@
+use std::{
+    backtrace::Backtrace,
+    fmt::{Debug, Display},
+    sync::Arc,
+};
+
+use hyper::{
+    header::{HeaderName, HeaderValue},
+    HeaderMap, Method, Uri,
+};
+use serde::Serialize;
+
+use crate::StatusCode;
+
+use super::{full, Response, Result, ToErrorResponse, ToResponse, WithReason};
+
+/// Error returned from endpoints, extractors, and responses
+///
+/// An error has a status code and message along with an optional source error, extra headers
+/// for the response, a machine readable code, and json details. Most errors can be converted
+/// with `?`, ex: a `std::io::Error` becomes a `404`, `403`, or `500` based on its kind. The
+/// `(u16, String)` tuples tela used before are still accepted through `.into()` or `?`.
+///
+/// Errors are given to the catch handler for their status. When there isn't one, errors with a
+/// code or details respond with json and others with the default error page.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, Error};
+///
+/// #[get("/config")]
+/// fn config() -> Result<String> {
+///     let text = std::fs::read_to_string("config.toml")?;
+///     if text.is_empty() {
+///         return Err(Error::new(503, "Not configured")
+///             .code("config.empty")
+///             .header("Retry-After", "120")
+///             .details(json!({ "file": "config.toml" })));
+///     }
+///     Ok(text)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Error(Box<Inner>);
+
+/// Fields of an [`Error`], boxed so results with an error stay small
+#[derive(Debug)]
+struct Inner {
+    status: u16,
+    message: String,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    headers: HeaderMap,
+    code: Option<String>,
+    details: Option<serde_json::Value>,
+    backtrace: Option<Arc<Backtrace>>,
+}
+
+impl Error {
+    pub fn new<M: Into<String>>(status: u16, message: M) -> Self {
+        Error(Box::new(Inner {
+            status,
+            message: message.into(),
+            source: None,
+            headers: HeaderMap::new(),
+            code: None,
+            details: None,
+            // Shown on the debug error page. Client errors are common and rarely need one.
+            backtrace: match cfg!(debug_assertions) && status >= 500 {
+                true => Some(Arc::new(Backtrace::force_capture())),
+                false => None,
+            },
+        }))
+    }
+
+    /// Error that caused this one
+    pub fn with_source<E: Into<Box<dyn std::error::Error + Send + Sync>>>(
+        mut self,
+        source: E,
+    ) -> Self {
+        self.0.source = Some(source.into());
+        self
+    }
+
+    /// Header added to the error response. Invalid headers are ignored.
+    pub fn header<K: TryInto<HeaderName>, V: TryInto<HeaderValue>>(
+        mut self,
+        key: K,
+        value: V,
+    ) -> Self {
+        if let (Ok(key), Ok(value)) = (key.try_into(), value.try_into()) {
+            self.0.headers.append(key, value);
+        }
+        self
+    }
+
+    /// Machine readable code of the error, ex: `user.not_found`
+    pub fn code<T: Into<String>>(mut self, code: T) -> Self {
+        self.0.code = Some(code.into());
+        self
+    }
+
+    /// Extra information that is sent with the error as json
+    pub fn details<T: Serialize>(mut self, details: T) -> Self {
+        self.0.details = serde_json::to_value(details).ok();
+        self
+    }
+
+    pub fn get_status(&self) -> u16 {
+        self.0.status
+    }
+
+    pub fn message(&self) -> &str {
+        &self.0.message
+    }
+
+    pub fn source(&self) -> Option<&(dyn std::error::Error + Send + Sync + 'static)> {
+        self.0.source.as_deref()
+    }
+
+    pub fn get_headers(&self) -> &HeaderMap {
+        &self.0.headers
+    }
+
+    pub fn get_code(&self) -> Option<&str> {
+        self.0.code.as_deref()
+    }
+
+    pub fn get_details(&self) -> Option<&serde_json::Value> {
+        self.0.details.as_ref()
+    }
+
+    /// Backtrace from where the error was created. Only captured for server errors, `5xx`, in
+    /// debug builds.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.0.backtrace.as_deref()
+    }
+
+    pub(crate) fn with_backtrace(mut self, backtrace: Option<Arc<Backtrace>>) -> Self {
+        if backtrace.is_some() {
+            self.0.backtrace = backtrace;
+        }
+        self
+    }
+
+    /// Message followed by the chain of source errors
+    pub fn reason(&self) -> String {
+        let mut reason = self.0.message.clone();
+        let mut source = self
+            .0
+            .source
+            .as_deref()
+            .map(|source| source as &(dyn std::error::Error + 'static));
+        while let Some(error) = source {
+            let cause = error.to_string();
+            if !reason.ends_with(&cause) {
+                reason.push_str(": ");
+                reason.push_str(&cause);
+            }
+            source = error.source();
+        }
+        reason
+    }
+
+    /// Whether the error should respond with json instead of the default error page
+    pub(crate) fn is_structured(&self) -> bool {
+        self.0.code.is_some() || self.0.details.is_some()
+    }
+
+    /// Add the error's headers to a response built for it
+    pub(crate) fn apply(&self, response: &mut Response) {
+        for (key, value) in self.0.headers.iter() {
+            response.headers_mut().append(key, value.clone());
+        }
+    }
+
+    fn json(&self) -> String {
+        let mut body = serde_json::json!({
+            "status": self.0.status,
+            "message": self.0.message,
+        });
+        if let Some(code) = &self.0.code {
+            body["code"] = serde_json::Value::String(code.clone());
+        }
+        if let Some(details) = &self.0.details {
+            body["details"] = details.clone();
+        }
+        body.to_string()
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.0.status, self.reason())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0
+            .source
+            .as_deref()
+            .map(|source| source as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl ToResponse for Error {
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        _headers: &HeaderMap,
+        _body: String,
+    ) -> Result<Response> {
+        self.to_error_response(0, String::new())
+    }
+}
+
+impl ToErrorResponse for Error {
+    fn to_error_response(self, _code: u16, _reason: String) -> Result<Response> {
+        let mut response = hyper::Response::builder()
+            .status(self.0.status)
+            .header("Content-Type", "application/json")
+            .tela_reason(&self.reason())
+            .body(full(self.json()))
+            .map_err(|err| Error::new(500, err.to_string()))?;
+        self.apply(&mut response);
+        Ok(response)
+    }
+}
+
+impl From<(u16, String)> for Error {
+    fn from((status, message): (u16, String)) -> Self {
+        Error::new(status, message)
+    }
+}
+
+impl From<(u16, &str)> for Error {
+    fn from((status, message): (u16, &str)) -> Self {
+        Error::new(status, message)
+    }
+}
+
+impl From<(StatusCode, String)> for Error {
+    fn from((status, message): (StatusCode, String)) -> Self {
+        Error::new(status as u16, message)
+    }
+}
+
+impl From<(StatusCode, &str)> for Error {
+    fn from((status, message): (StatusCode, &str)) -> Self {
+        Error::new(status as u16, message)
+    }
+}
+
+impl From<Error> for (u16, String) {
+    fn from(error: Error) -> Self {
+        (error.get_status(), error.reason())
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        let status = match error.kind() {
+            std::io::ErrorKind::NotFound => 404,
+            std::io::ErrorKind::PermissionDenied => 403,
+            _ => 500,
+        };
+        Error::new(status, error.to_string()).with_source(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        use serde_json::error::Category;
+        let status = match error.classify() {
+            Category::Syntax | Category::Eof => 400,
+            Category::Data => 422,
+            Category::Io => 500,
+        };
+        Error::new(status, error.to_string()).with_source(error)
+    }
+}
+
+impl From<serde_qs::Error> for Error {
+    fn from(error: serde_qs::Error) -> Self {
+        Error::new(422, error.to_string()).with_source(error)
+    }
+}
+
+impl From<std::str::Utf8Error> for Error {
+    fn from(error: std::str::Utf8Error) -> Self {
+        Error::new(400, error.to_string()).with_source(error)
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(error: std::string::FromUtf8Error) -> Self {
+        Error::new(400, error.to_string()).with_source(error)
+    }
+}
+
+impl From<std::num::ParseIntError> for Error {
+    fn from(error: std::num::ParseIntError) -> Self {
+        Error::new(400, error.to_string()).with_source(error)
+    }
+}
+
+impl From<std::num::ParseFloatError> for Error {
+    fn from(error: std::num::ParseFloatError) -> Self {
+        Error::new(400, error.to_string()).with_source(error)
+    }
+}
+
+impl From<std::fmt::Error> for Error {
+    fn from(error: std::fmt::Error) -> Self {
+        Error::new(500, error.to_string()).with_source(error)
+    }
+}
+
+impl From<hyper::Error> for Error {
+    fn from(error: hyper::Error) -> Self {
+        Error::new(500, error.to_string()).with_source(error)
+    }
+}
+
+impl From<hyper::http::Error> for Error {
+    fn from(error: hyper::http::Error) -> Self {
+        Error::new(500, error.to_string()).with_source(error)
+    }
+}
+
+#[cfg(feature = "tera")]
+impl From<tera::Error> for Error {
+    fn from(error: tera::Error) -> Self {
+        Error::new(500, error.to_string()).with_source(error)
+    }
+}
+
+#[cfg(feature = "handlebars")]
+impl From<handlebars::RenderError> for Error {
+    fn from(error: handlebars::RenderError) -> Self {
+        Error::new(500, error.to_string()).with_source(error)
+    }
+}
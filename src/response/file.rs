@@ -1,13 +1,43 @@
-use std::{ffi::OsStr, fs, path::Path};
+use std::{fs, path::Path};
 
-use bytes::Bytes;
-use http_body_util::Full;
-use hyper::{Method, Uri};
+use hyper::{HeaderMap, Method, Uri};
 
-use super::{Result, ToErrorResponse, ToResponse};
+use super::{
+    full, range::ranged_file_response, stream::file_body, Attachment, Response, Result,
+    ToErrorResponse, ToResponse, Validators, WithReason,
+};
 
+/// Response with the contents of a file. The content type is guessed from the file's extension.
+///
+/// Files are shown inline by default. Use [`File::attachment`] to have the client download them.
 pub struct File<T: Into<String> + Clone>(pub T);
 
+impl<T: Into<String> + Clone> File<T> {
+    /// Have the client download the file, keeping its name
+    pub fn attachment(self) -> Attachment<Self> {
+        let name = file_name(&self.0.clone().into());
+        Attachment::new(name, self)
+    }
+
+    /// Have the client display the file, keeping its name for when it is saved
+    pub fn inline(self) -> Attachment<Self> {
+        self.attachment().inline()
+    }
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn content_type(path: &str) -> String {
+    mime_guess::from_path(path)
+        .first_or_octet_stream()
+        .to_string()
+}
+
 impl<T: Into<String> + Clone> Into<String> for File<T> {
     fn into(self) -> String {
         match fs::read_to_string(Into::<String>::into(self.0)) {
@@ -22,46 +52,40 @@ impl<T: Into<String> + Clone> ToResponse for File<T> {
         self,
         _method: &Method,
         _uri: &Uri,
+        headers: &HeaderMap,
         _body: String,
-    ) -> Result<hyper::Response<Full<Bytes>>> {
-        let ct = match Path::new(&Into::<String>::into(self.0.clone()))
-            .extension()
-            .and_then(OsStr::to_str)
-        {
-            Some(extension) => match extension {
-                "html" | "htm" => "text/html",
-                "json" => "application/json",
-                _ => "text/plain",
-            },
-            _ => "text/plain",
-        };
+    ) -> Result<Response> {
+        let path = Into::<String>::into(self.0.clone());
+        let ct = content_type(&path);
 
-        Ok(hyper::Response::builder()
+        let file = fs::File::open(&path)?;
+        let validators = Validators::from_metadata(&file.metadata()?);
+
+        // Only the requested ranges are read; everything else is streamed from disk
+        if headers.contains_key("Range") {
+            return Ok(ranged_file_response(file, Some(ct), &validators, headers)?);
+        }
+
+        let length = file.metadata()?.len();
+        Ok(validators
+            .apply(hyper::Response::builder())
             .status(200)
             .header("Content-Type", ct)
-            .body(Full::new(Bytes::from(Into::<String>::into(self))))
+            .header("Accept-Ranges", "bytes")
+            .header("Content-Length", length)
+            .body(file_body(file))
             .unwrap())
     }
 }
 
 impl<T: Into<String> + Clone> ToErrorResponse for File<T> {
-    fn to_error_response(self, code: u16, reason: String) -> Result<hyper::Response<Full<Bytes>>> {
-        let ct = match Path::new(&Into::<String>::into(self.0.clone()))
-            .extension()
-            .and_then(OsStr::to_str)
-        {
-            Some(extension) => match extension {
-                "html" | "htm" => "text/html",
-                "json" => "application/json",
-                _ => "text/plain",
-            },
-            _ => "text/plain",
-        };
+    fn to_error_response(self, code: u16, reason: String) -> Result<Response> {
+        let path = Into::<String>::into(self.0);
         Ok(hyper::Response::builder()
             .status(code)
-            .header("Content-Type", ct)
-            .header("Wayfinder-Reason", reason)
-            .body(Full::new(Bytes::from(Into::<String>::into(self))))
+            .header("Content-Type", content_type(&path))
+            .tela_reason(&reason)
+            .body(full(fs::read(&path).unwrap_or_default()))
             .unwrap())
     }
 }
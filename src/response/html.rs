@@ -1,8 +1,6 @@
-use bytes::Bytes;
-use http_body_util::Full;
-use hyper::{Method, Uri};
+use hyper::{HeaderMap, Method, Uri};
 
-use super::{Result, ToErrorResponse, ToResponse};
+use super::{full, Response, Result, ToErrorResponse, ToResponse, WithReason};
 
 pub struct HTML<T: Into<String>>(pub T);
 
@@ -11,27 +9,24 @@ impl<T: Into<String>> ToResponse for HTML<T> {
         self,
         _method: &Method,
         _uri: &Uri,
+        _headers: &HeaderMap,
         _body: String,
-    ) -> Result<hyper::Response<Full<Bytes>>> {
+    ) -> Result<Response> {
         Ok(hyper::Response::builder()
             .status(200)
             .header("Content-Type", "text/html")
-            .body(Full::new(Bytes::from(Into::<String>::into(self.0))))
+            .body(full(Into::<String>::into(self.0)))
             .unwrap())
     }
 }
 
 impl<T: Into<String>> ToErrorResponse for HTML<T> {
-    fn to_error_response(
-        self,
-        code: u16,
-        reason: String,
-    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+    fn to_error_response(self, code: u16, reason: String) -> Result<Response> {
         Ok(hyper::Response::builder()
             .status(code)
             .header("Content-Type", "text/html")
-            .header("Wayfinder-Reason", reason)
-            .body(Full::new(Bytes::from(Into::<String>::into(self.0))))
+            .tela_reason(&reason)
+            .body(full(Into::<String>::into(self.0)))
             .unwrap())
     }
 }
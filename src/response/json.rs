@@ -1,11 +1,9 @@
-use bytes::Bytes;
-use http_body_util::Full;
-use hyper::{Method, Uri};
+use hyper::{HeaderMap, Method, Uri};
 use serde::{Deserialize, Serialize};
 
 use crate::errors::default_error_page;
 
-use super::{File, Result, ToErrorResponse, ToResponse};
+use super::{empty, full, Error, File, Response, Result, ToErrorResponse, ToResponse, WithReason};
 
 pub type Raw = serde_json::Value;
 
@@ -15,7 +13,7 @@ impl<T: Deserialize<'static> + Serialize> JSON<T> {
     pub fn from_str(value: String) -> Result<Self> {
         match serde_json::from_str::<T>(Box::leak(value.into_boxed_str())) {
             Ok(obj) => Ok(JSON(obj)),
-            _ => Err((500, "Failed to parse json from string".to_string())),
+            _ => Err(Error::new(500, "Failed to parse json from string")),
         }
     }
 
@@ -23,7 +21,7 @@ impl<T: Deserialize<'static> + Serialize> JSON<T> {
         let path = Into::<String>::into(value.0.clone());
         match serde_json::from_str::<T>(Box::leak(Into::<String>::into(value).into_boxed_str())) {
             Ok(obj) => Ok(JSON(obj)),
-            Err(err) => Err((
+            Err(err) => Err(Error::new(
                 500,
                 format!("Failed to parse json from file {:?}: {}", path, err),
             )),
@@ -36,13 +34,14 @@ impl<T: serde::Serialize> ToResponse for JSON<T> {
         self,
         method: &Method,
         uri: &Uri,
+        _headers: &HeaderMap,
         body: String,
-    ) -> Result<hyper::Response<Full<Bytes>>> {
+    ) -> Result<Response> {
         match serde_json::to_string(&self.0) {
             Ok(result) => Ok(hyper::Response::builder()
                 .status(200)
                 .header("Content-Type", "application/json")
-                .body(Full::new(Bytes::from(result)))
+                .body(full(result))
                 .unwrap()),
             Err(_) => Ok(default_error_page(
                 &500,
@@ -56,22 +55,19 @@ impl<T: serde::Serialize> ToResponse for JSON<T> {
 }
 
 impl<T: serde::Serialize> ToErrorResponse for JSON<T> {
-    fn to_error_response(self, code: u16, reason: String) -> Result<hyper::Response<Full<Bytes>>> {
+    fn to_error_response(self, code: u16, reason: String) -> Result<Response> {
         match serde_json::to_string(&self.0) {
             Ok(result) => Ok(hyper::Response::builder()
                 .status(code)
                 .header("Content-Type", "application/json")
-                .header("Wayfinder-Reason", reason)
-                .body(Full::new(Bytes::from(result)))
+                .tela_reason(&reason)
+                .body(full(result))
                 .unwrap()),
             Err(_) => Ok(hyper::Response::builder()
                 .status(500)
                 .header("Content-Type", "text/html")
-                .header(
-                    "Wayfinder-Reason",
-                    format!("{}{}", reason, "; Failed to parse json response"),
-                )
-                .body(Full::new(Bytes::new()))
+                .tela_reason(&format!("{}{}", reason, "; Failed to parse json response"))
+                .body(empty())
                 .unwrap()),
         }
     }
@@ -1,39 +1,108 @@
+mod attachment;
+mod cookie;
+mod custom;
+mod error;
 mod file;
 mod html;
 mod json;
+mod negotiate;
+mod problem;
+mod range;
 mod redirect;
+mod sse;
+mod stream;
 
 pub mod template;
 
+use std::convert::Infallible;
+
 use bytes::Bytes;
-use http_body_util::Full;
+use http_body_util::{combinators::UnsyncBoxBody, BodyExt, Empty, Full};
 
+pub use attachment::{content_disposition, Attachment, Disposition};
+pub use cookie::{Cookie, SameSite};
+pub use custom::{Custom, Headers, ResponseExt};
+pub use error::Error;
 pub use file::File;
 pub use html::HTML;
-use hyper::{Method, Uri};
+use hyper::{header::HeaderValue, HeaderMap, Method, Uri};
 pub use json::{Raw, JSON};
+pub use negotiate::{Format, Negotiate};
+pub use problem::Problem;
+pub use range::{ranged_response, ByteRange, Ranges, Validators};
 pub use redirect::Redirect;
+pub use sse::{Event, KeepAlive, Sse};
+pub use stream::{stream_body, Stream};
 pub use template::Template;
 
 use crate::StatusCode;
 
-pub type Result<T> = std::result::Result<T, (u16, String)>;
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Error of a streamed response body
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Body of every response. It can be fully in memory or streamed in chunks.
+pub type ResponseBody = UnsyncBoxBody<Bytes, BoxError>;
+
+pub type Response = hyper::Response<ResponseBody>;
+
+/// Response body from data that is fully in memory
+pub fn full<T: Into<Bytes>>(data: T) -> ResponseBody {
+    Full::new(data.into())
+        .map_err(|never: Infallible| -> BoxError { match never {} })
+        .boxed_unsync()
+}
+
+/// Header with the reason of an error response
+pub(crate) trait WithReason {
+    /// Add a `Tela-Reason` header. Reasons that aren't valid header values, ex: multi line panic
+    /// messages, are left out so building the response can't fail.
+    fn tela_reason(self, reason: &str) -> Self;
+}
+
+impl WithReason for hyper::http::response::Builder {
+    fn tela_reason(self, reason: &str) -> Self {
+        match HeaderValue::from_str(reason) {
+            Ok(value) => self.header("Tela-Reason", value),
+            Err(_) => self,
+        }
+    }
+}
+
+/// Response body without any data
+pub fn empty() -> ResponseBody {
+    Empty::<Bytes>::new()
+        .map_err(|never: Infallible| -> BoxError { match never {} })
+        .boxed_unsync()
+}
 
 pub trait ToResponse {
     fn to_response(
         self,
         method: &Method,
         uri: &Uri,
+        headers: &HeaderMap,
         body: String,
-    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>>;
+    ) -> Result<Response>;
 }
 
 pub trait ToErrorResponse {
-    fn to_error_response(
+    fn to_error_response(self, code: u16, reason: String) -> Result<Response>;
+
+    /// Same as [`ToErrorResponse::to_error_response`] with the headers of the request that
+    /// failed, ex: to follow its `Accept` header. Catch handlers respond with this.
+    fn to_error_response_with(
         self,
         code: u16,
         reason: String,
-    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>>;
+        _headers: &HeaderMap,
+    ) -> Result<Response>
+    where
+        Self: Sized,
+    {
+        self.to_error_response(code, reason)
+    }
 }
 
 impl<T: ToResponse> ToResponse for (u16, T) {
@@ -41,20 +110,16 @@ impl<T: ToResponse> ToResponse for (u16, T) {
         self,
         method: &Method,
         uri: &Uri,
+        headers: &HeaderMap,
         body: String,
-    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+    ) -> Result<Response> {
         let code = self.0;
-        self.1.to_response(method, uri, body).map(|result| {
-            let mut response = hyper::Response::builder()
-                .status(code)
-                .body(result.body().clone())
-                .unwrap();
-
-            // Copy over all headers
-            response.headers_mut().extend(result.headers().clone());
-
-            response
-        })
+        self.1
+            .to_response(method, uri, headers, body)
+            .map(|mut response| {
+                *response.status_mut() = hyper::StatusCode::from_u16(code).unwrap();
+                response
+            })
     }
 }
 
@@ -63,20 +128,16 @@ impl<T: ToResponse> ToResponse for (StatusCode, T) {
         self,
         method: &Method,
         uri: &Uri,
+        headers: &HeaderMap,
         body: String,
-    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+    ) -> Result<Response> {
         let code: u16 = self.0 as u16;
-        self.1.to_response(method, uri, body).map(|result| {
-            let mut response = hyper::Response::builder()
-                .status(code)
-                .body(result.body().clone())
-                .unwrap();
-
-            // Copy over all headers
-            response.headers_mut().extend(result.headers().clone());
-
-            response
-        })
+        self.1
+            .to_response(method, uri, headers, body)
+            .map(|mut response| {
+                *response.status_mut() = hyper::StatusCode::from_u16(code).unwrap();
+                response
+            })
     }
 }
 
@@ -85,41 +146,55 @@ impl<T: ToResponse> ToResponse for Result<T> {
         self,
         method: &Method,
         uri: &Uri,
+        headers: &HeaderMap,
         body: String,
-    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+    ) -> Result<Response> {
         match self {
-            Ok(response) => response.to_response(method, uri, body),
+            Ok(response) => response.to_response(method, uri, headers, body),
             Err(error) => Err(error),
         }
     }
 }
 
+/// Results with the `(code, reason)` error tuple are still accepted
+impl<T: ToResponse> ToResponse for Result<T, (u16, String)> {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: String,
+    ) -> Result<Response> {
+        match self {
+            Ok(response) => response.to_response(method, uri, headers, body),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
 impl ToResponse for String {
     fn to_response(
         self,
         _method: &Method,
         _uri: &Uri,
+        _headers: &HeaderMap,
         _body: String,
-    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+    ) -> Result<Response> {
         Ok(hyper::Response::builder()
             .status(200)
             .header("Content-Type", "text/plain")
-            .body(Full::new(Bytes::from(self)))
+            .body(full(self))
             .unwrap())
     }
 }
 
 impl ToErrorResponse for String {
-    fn to_error_response(
-        self,
-        code: u16,
-        reason: String,
-    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+    fn to_error_response(self, code: u16, reason: String) -> Result<Response> {
         Ok(hyper::Response::builder()
             .status(code)
             .header("Content-Type", "text/plain")
-            .header("Wayfinder-Reason", reason)
-            .body(Full::new(Bytes::from(self)))
+            .tela_reason(&reason)
+            .body(full(self))
             .unwrap())
     }
 }
@@ -129,27 +204,71 @@ impl ToResponse for &str {
         self,
         _method: &Method,
         _uri: &Uri,
+        _headers: &HeaderMap,
         _body: String,
-    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+    ) -> Result<Response> {
         Ok(hyper::Response::builder()
             .status(200)
             .header("Content-Type", "text/plain")
-            .body(Full::new(Bytes::from(self.to_string())))
+            .body(full(self.to_string()))
             .unwrap())
     }
 }
 
 impl ToErrorResponse for &str {
-    fn to_error_response(
-        self,
-        code: u16,
-        reason: String,
-    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+    fn to_error_response(self, code: u16, reason: String) -> Result<Response> {
         Ok(hyper::Response::builder()
             .status(code)
             .header("Content-Type", "text/plain")
-            .header("Wayfinder-Reason", reason)
-            .body(Full::new(Bytes::from(self.to_string())))
+            .tela_reason(&reason)
+            .body(full(self.to_string()))
+            .unwrap())
+    }
+}
+
+impl ToResponse for Bytes {
+    /// Sent as `application/octet-stream`. `Range` requests are answered with partial content.
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        headers: &HeaderMap,
+        _body: String,
+    ) -> Result<Response> {
+        Ok(ranged_response(
+            self,
+            Some("application/octet-stream".to_string()),
+            &Validators::default(),
+            headers,
+        ))
+    }
+}
+
+impl ToErrorResponse for Bytes {
+    fn to_error_response(self, code: u16, reason: String) -> Result<Response> {
+        Ok(hyper::Response::builder()
+            .status(code)
+            .header("Content-Type", "application/octet-stream")
+            .tela_reason(&reason)
+            .body(full(self))
             .unwrap())
     }
 }
+
+impl ToResponse for Vec<u8> {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: String,
+    ) -> Result<Response> {
+        Bytes::from(self).to_response(method, uri, headers, body)
+    }
+}
+
+impl ToErrorResponse for Vec<u8> {
+    fn to_error_response(self, code: u16, reason: String) -> Result<Response> {
+        Bytes::from(self).to_error_response(code, reason)
+    }
+}
@@ -0,0 +1,262 @@
+use hyper::{HeaderMap, Method, Uri};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::encoding::negotiate;
+
+use super::{full, Error, Response, Result, ToErrorResponse, ToResponse};
+
+/// Representation a [`Negotiate`] response can be rendered as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Html,
+    Text,
+    Xml,
+}
+
+impl Format {
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::Html => "text/html",
+            Format::Text => "text/plain",
+            Format::Xml => "application/xml",
+        }
+    }
+
+    fn from_media_type(media: &str) -> Option<Format> {
+        [Format::Json, Format::Html, Format::Text, Format::Xml]
+            .into_iter()
+            .find(|format| format.media_type() == media)
+    }
+}
+
+type Render<T> = Box<dyn FnOnce(&T) -> String + Send>;
+
+/// Response that renders a serializable value in the format the client's `Accept` header prefers
+///
+/// Values can be rendered as json, html, plain text, or xml. Formats are tried in the order they
+/// are given, which breaks ties in the client's preference, and requests that accept none of them
+/// are answered with `406 Not Acceptable`. Custom html and text renderings can replace the
+/// generated ones.
+///
+/// # Example
+/// ```
+/// use serde::Serialize;
+/// use tela::{prelude::*, response::{Format, Negotiate}};
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// #[get("/user")]
+/// fn user() -> Negotiate<User> {
+///     Negotiate::new(User { name: "tela".to_string() })
+///         .formats([Format::Json, Format::Html])
+///         .html(|value| format!("<h1>{}</h1>", value.name))
+/// }
+/// ```
+pub struct Negotiate<T: Serialize> {
+    value: T,
+    formats: Vec<Format>,
+    html: Option<Render<T>>,
+    text: Option<Render<T>>,
+}
+
+impl<T: Serialize> Negotiate<T> {
+    pub fn new(value: T) -> Self {
+        Negotiate {
+            value,
+            formats: vec![Format::Json, Format::Html, Format::Text, Format::Xml],
+            html: None,
+            text: None,
+        }
+    }
+
+    /// Formats that can be sent, in order of the server's preference
+    pub fn formats<const SIZE: usize>(mut self, formats: [Format; SIZE]) -> Self {
+        self.formats = formats.to_vec();
+        self
+    }
+
+    /// Render the html representation instead of generating it
+    pub fn html<F: FnOnce(&T) -> String + Send + 'static>(mut self, render: F) -> Self {
+        self.html = Some(Box::new(render));
+        self
+    }
+
+    /// Render the plain text representation instead of generating it
+    pub fn text<F: FnOnce(&T) -> String + Send + 'static>(mut self, render: F) -> Self {
+        self.text = Some(Box::new(render));
+        self
+    }
+
+    fn render(self, format: Format) -> Result<Response> {
+        let value = || {
+            serde_json::to_value(&self.value)
+                .map_err(|err| Error::new(500, "Failed to serialize response").with_source(err))
+        };
+        let body = match format {
+            Format::Json => serde_json::to_string(&self.value)
+                .map_err(|err| Error::new(500, "Failed to serialize response").with_source(err))?,
+            Format::Html => match self.html {
+                Some(render) => render(&self.value),
+                None => format!(
+                    "<!DOCTYPE html><html><head><meta charset=\"UTF-8\"/></head><body>{}</body></html>",
+                    html(&value()?)
+                ),
+            },
+            Format::Text => match self.text {
+                Some(render) => render(&self.value),
+                None => match value()? {
+                    Value::String(text) => text,
+                    Value::Null => String::new(),
+                    value @ (Value::Bool(_) | Value::Number(_)) => value.to_string(),
+                    value => serde_json::to_string_pretty(&value).unwrap_or_default(),
+                },
+            },
+            Format::Xml => format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>{}",
+                xml("response", &value()?)
+            ),
+        };
+
+        Ok(hyper::Response::builder()
+            .status(200)
+            .header("Content-Type", format.media_type())
+            .header("Vary", "Accept")
+            .body(full(body))
+            .unwrap())
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Generated html for a value: lists for arrays and description lists for objects
+fn html(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => escape(text),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Array(items) => format!(
+            "<ul>{}</ul>",
+            items
+                .iter()
+                .map(|item| format!("<li>{}</li>", html(item)))
+                .collect::<String>()
+        ),
+        Value::Object(fields) => format!(
+            "<dl>{}</dl>",
+            fields
+                .iter()
+                .map(|(key, value)| format!("<dt>{}</dt><dd>{}</dd>", escape(key), html(value)))
+                .collect::<String>()
+        ),
+    }
+}
+
+/// Generated xml for a value. Keys that aren't valid element names become `<field name="..">`.
+fn xml(name: &str, value: &Value) -> String {
+    let valid = name
+        .chars()
+        .next()
+        .map(|c| c.is_ascii_alphabetic() || c == '_')
+        .unwrap_or(false)
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        && !name.to_ascii_lowercase().starts_with("xml");
+    let (open, close) = match valid {
+        true => (name.to_string(), name.to_string()),
+        false => (
+            format!("field name=\"{}\"", escape(name)),
+            "field".to_string(),
+        ),
+    };
+
+    let content = match value {
+        Value::Null => String::new(),
+        Value::String(text) => escape(text),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Array(items) => items.iter().map(|item| xml("item", item)).collect(),
+        Value::Object(fields) => fields.iter().map(|(key, value)| xml(key, value)).collect(),
+    };
+    format!("<{}>{}</{}>", open, content, close)
+}
+
+impl<T: Serialize> ToResponse for Negotiate<T> {
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        headers: &HeaderMap,
+        _body: String,
+    ) -> Result<Response> {
+        let supported = self
+            .formats
+            .iter()
+            .map(|format| format.media_type())
+            .collect::<Vec<&str>>();
+        match negotiate(headers, &supported).and_then(Format::from_media_type) {
+            Some(format) => self.render(format),
+            None => Err(Error::new(
+                406,
+                format!(
+                    "Not acceptable; available formats are {}",
+                    supported.join(", ")
+                ),
+            )),
+        }
+    }
+}
+
+impl<T: Serialize> Negotiate<T> {
+    fn error(self, format: Format, code: u16, reason: String) -> Result<Response> {
+        let mut response = self.render(format)?;
+        *response.status_mut() =
+            hyper::StatusCode::from_u16(code).map_err(|err| Error::new(500, err.to_string()))?;
+        if let Ok(reason) = reason.parse() {
+            response.headers_mut().insert("Tela-Reason", reason);
+        }
+        Ok(response)
+    }
+
+    fn first(&self) -> Format {
+        self.formats.first().copied().unwrap_or(Format::Json)
+    }
+}
+
+impl<T: Serialize> ToErrorResponse for Negotiate<T> {
+    /// Without the request's headers the first format is used
+    fn to_error_response(self, code: u16, reason: String) -> Result<Response> {
+        let format = self.first();
+        self.error(format, code, reason)
+    }
+
+    /// Follows the request's `Accept` header. The error is still sent, in the first format, when
+    /// none of the formats are acceptable.
+    fn to_error_response_with(
+        self,
+        code: u16,
+        reason: String,
+        headers: &HeaderMap,
+    ) -> Result<Response> {
+        let supported = self
+            .formats
+            .iter()
+            .map(|format| format.media_type())
+            .collect::<Vec<&str>>();
+        let format = negotiate(headers, &supported)
+            .and_then(Format::from_media_type)
+            .unwrap_or_else(|| self.first());
+        self.error(format, code, reason)
+    }
+}
@@ -0,0 +1,189 @@
+use hyper::{HeaderMap, Method, Uri};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::StatusCode;
+
+use super::{full, Error, Response, Result, ToErrorResponse, ToResponse};
+
+/// RFC 7807 `application/problem+json` response
+///
+/// The `type` defaults to `about:blank` and the `title` to the message of the status code. Any
+/// extension members are added next to the standard members.
+///
+/// The server responds to unhandled errors with a problem when the client's `Accept` header
+/// prefers json, or always when `Server::problem_details` is enabled.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, response::Problem};
+///
+/// #[post("/transfer")]
+/// fn transfer() -> Problem {
+///     Problem::new(403)
+///         .kind("https://example.com/probs/out-of-credit")
+///         .title("You do not have enough credit.")
+///         .detail("Your current balance is 30, but that costs 50.")
+///         .instance("/account/12345/msgs/abc")
+///         .extension("balance", 30)
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    kind: String,
+    title: String,
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+    #[serde(flatten)]
+    extensions: Map<String, Value>,
+}
+
+impl Problem {
+    pub fn new(status: u16) -> Self {
+        Problem {
+            kind: "about:blank".to_string(),
+            title: StatusCode::from(status).message(),
+            status,
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// URI reference that identifies the problem type
+    pub fn kind<T: Into<String>>(mut self, kind: T) -> Self {
+        self.kind = kind.into();
+        self
+    }
+
+    /// Short summary of the problem type
+    pub fn title<T: Into<String>>(mut self, title: T) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Explanation specific to this occurrence of the problem
+    pub fn detail<T: Into<String>>(mut self, detail: T) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// URI reference that identifies this occurrence of the problem
+    pub fn instance<T: Into<String>>(mut self, instance: T) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Extra member of the problem. Members named after a standard member are ignored.
+    pub fn extension<K: Into<String>, V: Serialize>(mut self, key: K, value: V) -> Self {
+        let key = key.into();
+        if !["type", "title", "status", "detail", "instance"].contains(&key.as_str()) {
+            if let Ok(value) = serde_json::to_value(value) {
+                self.extensions.insert(key, value);
+            }
+        }
+        self
+    }
+
+    pub fn get_status(&self) -> u16 {
+        self.status
+    }
+
+    fn response(&self) -> Result<Response> {
+        hyper::Response::builder()
+            .status(self.status)
+            .header("Content-Type", "application/problem+json")
+            .body(full(serde_json::to_string(self).unwrap_or_default()))
+            .map_err(|err| {
+                Error::new(500, format!("Invalid problem status {}", self.status)).with_source(err)
+            })
+    }
+}
+
+impl From<&Error> for Problem {
+    /// The error's reason becomes the detail and its code and details become extensions
+    fn from(error: &Error) -> Self {
+        let mut problem = Problem::new(error.get_status()).detail(error.reason());
+        if let Some(code) = error.get_code() {
+            problem = problem.extension("code", code);
+        }
+        if let Some(details) = error.get_details() {
+            problem = problem.extension("details", details);
+        }
+        problem
+    }
+}
+
+impl From<Error> for Problem {
+    fn from(error: Error) -> Self {
+        Problem::from(&error)
+    }
+}
+
+impl ToResponse for Problem {
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        _headers: &HeaderMap,
+        _body: String,
+    ) -> Result<Response> {
+        self.response()
+    }
+}
+
+impl ToErrorResponse for Problem {
+    fn to_error_response(self, _code: u16, reason: String) -> Result<Response> {
+        let mut response = self.response()?;
+        if let Ok(reason) = reason.parse() {
+            response.headers_mut().insert("Tela-Reason", reason);
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{HeaderMap, Method, Uri};
+
+    use super::{Problem, ToErrorResponse, ToResponse};
+
+    #[test]
+    fn response_is_problem_json() {
+        let response = Problem::new(403)
+            .to_response(
+                &Method::GET,
+                &Uri::from_static("/"),
+                &HeaderMap::new(),
+                String::new(),
+            )
+            .unwrap();
+        assert_eq!(response.status(), 403);
+        assert_eq!(
+            response.headers().get("Content-Type").unwrap(),
+            "application/problem+json"
+        );
+    }
+
+    #[test]
+    fn invalid_status_is_an_error() {
+        let error = Problem::new(1000)
+            .to_response(
+                &Method::GET,
+                &Uri::from_static("/"),
+                &HeaderMap::new(),
+                String::new(),
+            )
+            .unwrap_err();
+        assert_eq!(error.get_status(), 500);
+
+        let error = Problem::new(42)
+            .to_error_response(42, String::new())
+            .unwrap_err();
+        assert_eq!(error.get_status(), 500);
+    }
+}
@@ -0,0 +1,365 @@
+use std::{
+    fs::{File, Metadata},
+    io::{self, Read, Seek, SeekFrom},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+use hyper::HeaderMap;
+
+use super::{empty, full, stream::file_body, Response, ResponseBody};
+
+/// Max number of ranges accepted in a single `Range` header. Anything above this is
+/// treated as if no range was requested.
+const MAX_RANGES: usize = 32;
+
+/// Inclusive byte range resolved against the length of the content.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    pub fn content_range(&self, length: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, length)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Ranges {
+    /// No range requested, the range is invalid, or the range is ignored
+    Full,
+    /// One or more satisfiable ranges
+    Partial(Vec<ByteRange>),
+    /// None of the requested ranges overlap the content
+    Unsatisfiable,
+}
+
+impl Ranges {
+    /// Parse the value of a `Range` header against the content length.
+    ///
+    /// Invalid headers and unknown units are ignored per RFC 9110 and result in `Ranges::Full`.
+    pub fn parse(value: &str, length: u64) -> Ranges {
+        let specs = match value.trim().strip_prefix("bytes=") {
+            Some(specs) => specs,
+            None => return Ranges::Full,
+        };
+
+        let mut ranges = Vec::new();
+        let mut count = 0;
+        for spec in specs.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            count += 1;
+            if count > MAX_RANGES {
+                return Ranges::Full;
+            }
+
+            let (start, end) = match spec.split_once('-') {
+                Some(bounds) => bounds,
+                None => return Ranges::Full,
+            };
+
+            match (start.trim(), end.trim()) {
+                ("", suffix) => match suffix.parse::<u64>() {
+                    Ok(0) => {}
+                    Ok(suffix) if length > 0 => ranges.push(ByteRange {
+                        start: length.saturating_sub(suffix),
+                        end: length - 1,
+                    }),
+                    Ok(_) => {}
+                    Err(_) => return Ranges::Full,
+                },
+                (start, "") => match start.parse::<u64>() {
+                    Ok(start) if start < length => ranges.push(ByteRange {
+                        start,
+                        end: length - 1,
+                    }),
+                    Ok(_) => {}
+                    Err(_) => return Ranges::Full,
+                },
+                (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+                    (Ok(start), Ok(end)) if start <= end => {
+                        if start < length {
+                            ranges.push(ByteRange {
+                                start,
+                                end: end.min(length - 1),
+                            })
+                        }
+                    }
+                    _ => return Ranges::Full,
+                },
+            }
+        }
+
+        if count == 0 {
+            Ranges::Full
+        } else if ranges.is_empty() {
+            Ranges::Unsatisfiable
+        } else {
+            Ranges::Partial(ranges)
+        }
+    }
+}
+
+/// Validators used to answer conditional range requests (`If-Range`)
+#[derive(Debug, Clone, Default)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    pub fn from_metadata(metadata: &Metadata) -> Self {
+        let last_modified = metadata.modified().ok();
+        let etag = last_modified
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|modified| format!("\"{:x}-{:x}\"", metadata.len(), modified.as_nanos()));
+
+        Validators {
+            etag,
+            last_modified,
+        }
+    }
+
+    /// Whether the `If-Range` condition still matches the current representation.
+    ///
+    /// Weak entity tags never match as a range must come from the exact same bytes.
+    pub fn if_range(&self, value: &str) -> bool {
+        let value = value.trim();
+        if value.starts_with('"') {
+            return self.etag.as_deref() == Some(value);
+        }
+        if value.starts_with("W/") {
+            return false;
+        }
+
+        match (httpdate::parse_http_date(value), self.last_modified) {
+            (Ok(date), Some(modified)) => {
+                httpdate::fmt_http_date(date) == httpdate::fmt_http_date(modified)
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn apply(
+        &self,
+        mut builder: hyper::http::response::Builder,
+    ) -> hyper::http::response::Builder {
+        if let Some(etag) = &self.etag {
+            builder = builder.header("ETag", etag);
+        }
+        if let Some(modified) = self.last_modified {
+            builder = builder.header("Last-Modified", httpdate::fmt_http_date(modified));
+        }
+        builder
+    }
+}
+
+/// Build a response for the given content that honors the `Range` and `If-Range` request headers.
+///
+/// Responds with `206 Partial Content` for satisfiable ranges, `416 Range Not Satisfiable` when
+/// none of the ranges overlap the content, and `200 OK` with the full content otherwise. A single
+/// range is sent as is while multiple ranges are sent as `multipart/byteranges`.
+pub fn ranged_response(
+    data: Bytes,
+    content_type: Option<String>,
+    validators: &Validators,
+    headers: &HeaderMap,
+) -> Response {
+    let length = data.len() as u64;
+    let slice = data.clone();
+    respond(
+        length,
+        content_type,
+        validators,
+        headers,
+        |range| Ok(slice.slice(range.start as usize..=range.end as usize)),
+        || full(data),
+    )
+    .unwrap()
+}
+
+/// Same as [`ranged_response`] but only the requested ranges are read from the file. The full
+/// file is streamed from disk.
+pub(crate) fn ranged_file_response(
+    mut file: File,
+    content_type: Option<String>,
+    validators: &Validators,
+    headers: &HeaderMap,
+) -> io::Result<Response> {
+    let length = file.metadata()?.len();
+    let mut reader = file.try_clone()?;
+    respond(
+        length,
+        content_type,
+        validators,
+        headers,
+        |range| {
+            let mut buffer = vec![0; (range.end - range.start + 1) as usize];
+            reader.seek(SeekFrom::Start(range.start))?;
+            reader.read_exact(&mut buffer)?;
+            Ok(Bytes::from(buffer))
+        },
+        move || {
+            let _ = file.rewind();
+            file_body(file)
+        },
+    )
+}
+
+/// Resolve the requested ranges and build the response, reading each range with `read`
+fn respond<R, F>(
+    length: u64,
+    content_type: Option<String>,
+    validators: &Validators,
+    headers: &HeaderMap,
+    mut read: R,
+    body: F,
+) -> io::Result<Response>
+where
+    R: FnMut(ByteRange) -> io::Result<Bytes>,
+    F: FnOnce() -> ResponseBody,
+{
+    let mut ranges = match headers.get("Range").and_then(|r| r.to_str().ok()) {
+        Some(range) => Ranges::parse(range, length),
+        None => Ranges::Full,
+    };
+
+    if let Some(condition) = headers.get("If-Range").and_then(|r| r.to_str().ok()) {
+        if !validators.if_range(condition) {
+            ranges = Ranges::Full;
+        }
+    }
+
+    let builder = validators.apply(hyper::Response::builder().header("Accept-Ranges", "bytes"));
+
+    Ok(match ranges {
+        Ranges::Full => {
+            let mut builder = builder.status(200).header("Content-Length", length);
+            if let Some(ct) = &content_type {
+                builder = builder.header("Content-Type", ct);
+            }
+            builder.body(body()).unwrap()
+        }
+        Ranges::Unsatisfiable => builder
+            .status(416)
+            .header("Content-Range", format!("bytes */{}", length))
+            .body(empty())
+            .unwrap(),
+        Ranges::Partial(ranges) if ranges.len() == 1 => {
+            let range = ranges[0];
+            let mut builder = builder
+                .status(206)
+                .header("Content-Range", range.content_range(length));
+            if let Some(ct) = &content_type {
+                builder = builder.header("Content-Type", ct);
+            }
+            builder.body(full(read(range)?)).unwrap()
+        }
+        Ranges::Partial(ranges) => {
+            let boundary = boundary();
+            let mut body = Vec::new();
+            for range in ranges.iter() {
+                body.extend(format!("\r\n--{}\r\n", boundary).as_bytes());
+                if let Some(ct) = &content_type {
+                    body.extend(format!("Content-Type: {}\r\n", ct).as_bytes());
+                }
+                body.extend(
+                    format!("Content-Range: {}\r\n\r\n", range.content_range(length)).as_bytes(),
+                );
+                body.extend(read(*range)?);
+            }
+            body.extend(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+            builder
+                .status(206)
+                .header(
+                    "Content-Type",
+                    format!("multipart/byteranges; boundary={}", boundary),
+                )
+                .body(full(body))
+                .unwrap()
+        }
+    })
+}
+
+fn boundary() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!("tela-byteranges-{:x}", nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ByteRange, Ranges};
+
+    fn range(start: u64, end: u64) -> ByteRange {
+        ByteRange { start, end }
+    }
+
+    #[test]
+    fn single_ranges() {
+        assert_eq!(
+            Ranges::parse("bytes=0-499", 1000),
+            Ranges::Partial(vec![range(0, 499)])
+        );
+        assert_eq!(
+            Ranges::parse("bytes=500-", 1000),
+            Ranges::Partial(vec![range(500, 999)])
+        );
+        assert_eq!(
+            Ranges::parse("bytes=-200", 1000),
+            Ranges::Partial(vec![range(800, 999)])
+        );
+        assert_eq!(
+            Ranges::parse("bytes=900-2000", 1000),
+            Ranges::Partial(vec![range(900, 999)])
+        );
+    }
+
+    #[test]
+    fn suffix_larger_than_content() {
+        assert_eq!(
+            Ranges::parse("bytes=-5000", 1000),
+            Ranges::Partial(vec![range(0, 999)])
+        );
+    }
+
+    #[test]
+    fn multiple_ranges() {
+        assert_eq!(
+            Ranges::parse("bytes=0-9, 20-29,-5", 100),
+            Ranges::Partial(vec![range(0, 9), range(20, 29), range(95, 99)])
+        );
+    }
+
+    #[test]
+    fn unsatisfiable() {
+        assert_eq!(Ranges::parse("bytes=1000-", 1000), Ranges::Unsatisfiable);
+        assert_eq!(Ranges::parse("bytes=-0", 1000), Ranges::Unsatisfiable);
+        assert_eq!(Ranges::parse("bytes=0-10", 0), Ranges::Unsatisfiable);
+    }
+
+    #[test]
+    fn invalid_ranges_are_ignored() {
+        assert_eq!(Ranges::parse("items=0-10", 1000), Ranges::Full);
+        assert_eq!(Ranges::parse("bytes=10-5", 1000), Ranges::Full);
+        assert_eq!(Ranges::parse("bytes=abc", 1000), Ranges::Full);
+        assert_eq!(Ranges::parse("bytes=", 1000), Ranges::Full);
+        assert_eq!(Ranges::parse("bytes=a-b", 1000), Ranges::Full);
+    }
+
+    #[test]
+    fn too_many_ranges_are_ignored() {
+        let specs = (0..33)
+            .map(|i| format!("{}-{}", i, i))
+            .collect::<Vec<_>>()
+            .join(",");
+        assert_eq!(
+            Ranges::parse(&format!("bytes={}", specs), 1000),
+            Ranges::Full
+        );
+    }
+}
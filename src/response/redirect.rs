@@ -1,8 +1,6 @@
-use bytes::Bytes;
-use http_body_util::Full;
-use hyper::{Method, Uri};
+use hyper::{HeaderMap, Method, Uri};
 
-use super::{Result, ToErrorResponse, ToResponse};
+use super::{empty, Response, Result, ToErrorResponse, ToResponse, WithReason};
 
 pub struct Redirect<const CODE: u16 = 302>(pub String);
 
@@ -13,26 +11,22 @@ impl<const CODE: u16> Redirect<CODE> {
 }
 
 impl<const CODE: u16> ToErrorResponse for Redirect<CODE> {
-    fn to_error_response(
-        self,
-        code: u16,
-        reason: String,
-    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+    fn to_error_response(self, code: u16, reason: String) -> Result<Response> {
         if ![301, 302, 303, 307, 308].contains(&code) {
             Ok(hyper::Response::builder()
                 .status(302)
                 .header("Content-Type", "text/html")
                 .header("Location", self.0.to_string())
-                .header("Tela-Reason", reason)
-                .body(Full::new(Bytes::new()))
+                .tela_reason(&reason)
+                .body(empty())
                 .unwrap())
         } else {
             Ok(hyper::Response::builder()
                 .status(code)
                 .header("Content-Type", "text/html")
-                .header("Tela-Reason", reason)
+                .tela_reason(&reason)
                 .header("Location", self.0.to_string())
-                .body(Full::new(Bytes::new()))
+                .body(empty())
                 .unwrap())
         }
     }
@@ -43,21 +37,22 @@ impl<const CODE: u16> ToResponse for Redirect<CODE> {
         self,
         _method: &Method,
         _uri: &Uri,
+        _headers: &HeaderMap,
         _body: String,
-    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+    ) -> Result<Response> {
         if ![301, 302, 303, 307, 308].contains(&CODE) {
             Ok(hyper::Response::builder()
                 .status(302)
                 .header("Content-Type", "text/html")
                 .header("Location", self.0.to_string())
-                .body(Full::new(Bytes::new()))
+                .body(empty())
                 .unwrap())
         } else {
             Ok(hyper::Response::builder()
                 .status(CODE)
                 .header("Content-Type", "text/html")
                 .header("Location", self.0.to_string())
-                .body(Full::new(Bytes::new()))
+                .body(empty())
                 .unwrap())
         }
     }
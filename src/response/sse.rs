@@ -0,0 +1,239 @@
+use std::{fmt::Write, pin::Pin, time::Duration};
+
+use bytes::Bytes;
+use futures_util::StreamExt;
+use hyper::{HeaderMap, Method, Uri};
+use tokio::time::{interval_at, Instant, MissedTickBehavior};
+
+use super::{stream_body, Error, Response, Result, ToResponse};
+
+/// Single server-sent event
+///
+/// # Example
+/// ```
+/// use tela::response::Event;
+///
+/// let event = Event::new()
+///     .event("update")
+///     .id("42")
+///     .data("{\"visitors\": 3}");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Event {
+    event: Option<String>,
+    data: Option<String>,
+    id: Option<String>,
+    retry: Option<Duration>,
+    comment: Option<String>,
+}
+
+impl Event {
+    pub fn new() -> Self {
+        Event::default()
+    }
+
+    /// Name of the event. Clients listen for it with `addEventListener(name, ...)`.
+    pub fn event<T: Into<String>>(mut self, event: T) -> Self {
+        self.event = Some(single_line(event.into()));
+        self
+    }
+
+    /// Data of the event. Multiple lines are sent as multiple `data` fields.
+    pub fn data<T: Into<String>>(mut self, data: T) -> Self {
+        self.data = Some(data.into());
+        self
+    }
+
+    /// Id the client sends back in the `Last-Event-ID` header when it reconnects
+    pub fn id<T: Into<String>>(mut self, id: T) -> Self {
+        self.id = Some(single_line(id.into()).replace('\0', ""));
+        self
+    }
+
+    /// How long the client should wait before reconnecting
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Comment that is ignored by the client
+    pub fn comment<T: Into<String>>(mut self, comment: T) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    fn to_bytes(&self) -> Bytes {
+        let mut buffer = String::new();
+        if let Some(comment) = &self.comment {
+            for line in comment.split('\n') {
+                let _ = writeln!(buffer, ":{}", line);
+            }
+        }
+        if let Some(event) = &self.event {
+            let _ = writeln!(buffer, "event: {}", event);
+        }
+        if let Some(id) = &self.id {
+            let _ = writeln!(buffer, "id: {}", id);
+        }
+        if let Some(retry) = self.retry {
+            let _ = writeln!(buffer, "retry: {}", retry.as_millis());
+        }
+        if let Some(data) = &self.data {
+            // `lines` drops a trailing empty line which must still be sent as a field
+            for line in data.split('\n') {
+                let _ = writeln!(buffer, "data: {}", line.strip_suffix('\r').unwrap_or(line));
+            }
+        }
+        buffer.push('\n');
+        Bytes::from(buffer)
+    }
+}
+
+impl From<String> for Event {
+    fn from(data: String) -> Self {
+        Event::new().data(data)
+    }
+}
+
+impl From<&str> for Event {
+    fn from(data: &str) -> Self {
+        Event::new().data(data)
+    }
+}
+
+fn single_line(value: String) -> String {
+    value.replace(['\r', '\n'], "")
+}
+
+/// Comment frames sent while no events are, so proxies don't close idle connections
+#[derive(Debug, Clone)]
+pub struct KeepAlive {
+    interval: Duration,
+    text: String,
+}
+
+impl Default for KeepAlive {
+    fn default() -> Self {
+        KeepAlive::new()
+    }
+}
+
+impl KeepAlive {
+    /// Send an empty comment after 15 seconds without an event
+    pub fn new() -> Self {
+        KeepAlive {
+            interval: Duration::from_secs(15),
+            text: String::new(),
+        }
+    }
+
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Text of the keep-alive comment
+    pub fn text<T: Into<String>>(mut self, text: T) -> Self {
+        self.text = single_line(text.into());
+        self
+    }
+}
+
+/// Server-sent events response built from a stream of events
+///
+/// The connection stays open until the stream ends. Keep-alive comments are sent every 15
+/// seconds by default; see [`Sse::keep_alive`].
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use tela::{prelude::*, request::LastEventId, response::{Event, Sse}};
+///
+/// #[get("/ticks")]
+/// fn ticks(last: Option<LastEventId>) -> Sse<impl futures::Stream<Item = Event>> {
+///     let start = last.and_then(|id| id.0.parse::<u64>().ok()).unwrap_or(0);
+///     Sse::new(futures::stream::unfold(start, |tick| async move {
+///         tokio::time::sleep(Duration::from_secs(1)).await;
+///         Some((Event::new().id(tick.to_string()).data("tick"), tick + 1))
+///     }))
+/// }
+/// ```
+pub struct Sse<S> {
+    stream: S,
+    keep_alive: Option<KeepAlive>,
+}
+
+impl<S, E> Sse<S>
+where
+    S: futures_util::Stream<Item = E> + Send + 'static,
+    E: Into<Event>,
+{
+    pub fn new(stream: S) -> Self {
+        Sse {
+            stream,
+            keep_alive: Some(KeepAlive::new()),
+        }
+    }
+
+    /// Change the keep-alive comments or disable them with `None`
+    pub fn keep_alive(mut self, keep_alive: Option<KeepAlive>) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+}
+
+impl<S, E> ToResponse for Sse<S>
+where
+    S: futures_util::Stream<Item = E> + Send + 'static,
+    E: Into<Event>,
+{
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        _headers: &HeaderMap,
+        _body: String,
+    ) -> Result<Response> {
+        let events = self
+            .stream
+            .map(|event| Into::<Event>::into(event).to_bytes());
+
+        let body = match self.keep_alive {
+            Some(keep_alive) => {
+                let comment = Event::new().comment(keep_alive.text).to_bytes();
+                let mut ticks =
+                    interval_at(Instant::now() + keep_alive.interval, keep_alive.interval);
+                ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+                let events: Pin<Box<dyn futures_util::Stream<Item = Bytes> + Send>> =
+                    Box::pin(events);
+                stream_body(futures_util::stream::unfold(
+                    (events, ticks),
+                    move |(mut events, mut ticks)| {
+                        let comment = comment.clone();
+                        async move {
+                            tokio::select! {
+                                event = events.next() => {
+                                    // Only send keep-alives while the connection is idle
+                                    ticks.reset();
+                                    event.map(|event| (event, (events, ticks)))
+                                }
+                                _ = ticks.tick() => Some((comment, (events, ticks))),
+                            }
+                        }
+                    },
+                ))
+            }
+            None => stream_body(events),
+        };
+
+        hyper::Response::builder()
+            .status(200)
+            .header("Content-Type", "text/event-stream")
+            .header("Cache-Control", "no-cache")
+            .body(body)
+            .map_err(|err| {
+                Error::new(500, "Failed to build event stream response").with_source(err)
+            })
+    }
+}
@@ -0,0 +1,165 @@
+use bytes::Bytes;
+use futures_util::StreamExt;
+use http_body_util::{BodyExt, StreamBody};
+use hyper::{body::Frame, HeaderMap, Method, Uri};
+use tokio::io::AsyncReadExt;
+
+use super::{
+    BoxError, Error, Response, ResponseBody, Result, ToErrorResponse, ToResponse, WithReason,
+};
+
+/// Size of the chunks files are streamed in
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Response that is sent in chunks as the stream yields them
+///
+/// The body is sent with chunked transfer encoding so it never needs to be fully in memory.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, response::Stream};
+///
+/// #[get("/report.csv")]
+/// fn report() -> Stream<impl futures::Stream<Item = String>> {
+///     Stream::new(futures::stream::iter((0..100_000).map(|i| format!("{},{}\n", i, i * i))))
+///         .content_type("text/csv")
+/// }
+/// ```
+pub struct Stream<S> {
+    stream: S,
+    content_type: String,
+}
+
+impl<S, T> Stream<S>
+where
+    S: futures_util::Stream<Item = T> + Send + 'static,
+    T: Into<Bytes>,
+{
+    /// Stream of chunks with a content type of `application/octet-stream`
+    pub fn new(stream: S) -> Self {
+        Stream {
+            stream,
+            content_type: "application/octet-stream".to_string(),
+        }
+    }
+
+    pub fn content_type<C: Into<String>>(mut self, content_type: C) -> Self {
+        self.content_type = content_type.into();
+        self
+    }
+}
+
+/// Response body that sends each item of the stream as a chunk
+pub fn stream_body<S, T>(stream: S) -> ResponseBody
+where
+    S: futures_util::Stream<Item = T> + Send + 'static,
+    T: Into<Bytes>,
+{
+    StreamBody::new(stream.map(|chunk| Ok::<_, BoxError>(Frame::data(chunk.into())))).boxed_unsync()
+}
+
+/// Response body that reads the file in chunks as it is sent
+pub(crate) fn file_body(file: std::fs::File) -> ResponseBody {
+    let file = tokio::fs::File::from_std(file);
+    let stream = futures_util::stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        let mut buffer = vec![0; CHUNK_SIZE];
+        match file.read(&mut buffer).await {
+            Ok(0) => None,
+            Ok(read) => {
+                buffer.truncate(read);
+                Some((Ok(Frame::data(Bytes::from(buffer))), Some(file)))
+            }
+            Err(err) => Some((Err(BoxError::from(err)), None)),
+        }
+    });
+    StreamBody::new(stream).boxed_unsync()
+}
+
+impl<S, T> ToResponse for Stream<S>
+where
+    S: futures_util::Stream<Item = T> + Send + 'static,
+    T: Into<Bytes>,
+{
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        _headers: &HeaderMap,
+        _body: String,
+    ) -> Result<Response> {
+        let content_type = self.content_type.clone();
+        hyper::Response::builder()
+            .status(200)
+            .header("Content-Type", self.content_type)
+            .body(stream_body(self.stream))
+            .map_err(|err| {
+                Error::new(500, format!("Invalid stream content type {}", content_type))
+                    .with_source(err)
+            })
+    }
+}
+
+impl<S, T> ToErrorResponse for Stream<S>
+where
+    S: futures_util::Stream<Item = T> + Send + 'static,
+    T: Into<Bytes>,
+{
+    fn to_error_response(self, code: u16, reason: String) -> Result<Response> {
+        let content_type = self.content_type.clone();
+        hyper::Response::builder()
+            .status(code)
+            .header("Content-Type", self.content_type)
+            .tela_reason(&reason)
+            .body(stream_body(self.stream))
+            .map_err(|err| {
+                Error::new(500, format!("Invalid stream content type {}", content_type))
+                    .with_source(err)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{HeaderMap, Method, Uri};
+
+    use super::{Stream, ToErrorResponse, ToResponse};
+
+    fn chunks() -> impl futures_util::Stream<Item = &'static str> {
+        futures_util::stream::iter(["a", "b"])
+    }
+
+    #[test]
+    fn content_type_is_set() {
+        let response = Stream::new(chunks())
+            .content_type("text/csv")
+            .to_response(
+                &Method::GET,
+                &Uri::from_static("/"),
+                &HeaderMap::new(),
+                String::new(),
+            )
+            .unwrap();
+        assert_eq!(response.headers().get("Content-Type").unwrap(), "text/csv");
+    }
+
+    #[test]
+    fn invalid_content_type_is_an_error() {
+        let error = Stream::new(chunks())
+            .content_type("text/csv\r\nSet-Cookie: a=b")
+            .to_response(
+                &Method::GET,
+                &Uri::from_static("/"),
+                &HeaderMap::new(),
+                String::new(),
+            )
+            .unwrap_err();
+        assert_eq!(error.get_status(), 500);
+
+        let error = Stream::new(chunks())
+            .content_type("text/csv\n")
+            .to_error_response(404, String::new())
+            .unwrap_err();
+        assert_eq!(error.get_status(), 500);
+    }
+}
@@ -5,9 +5,10 @@ cfg_if::cfg_if! {
 use std::{collections::BTreeMap, ffi::OsStr, path::Path, sync::RwLock};
 
 use lazy_static::lazy_static;
-use crate::StripPath;
+use crate::{assets::Source, StripPath};
 
-use super::{TemplateEngine, TreeToTemplateContext, Result};
+use super::{Result, TemplateEngine, TreeToTemplateContext};
+use crate::response::Error;
 
 lazy_static! {
     pub static ref HANDLEBARS: RwLock<
@@ -31,14 +32,30 @@ impl TemplateEngine for Handlebars {
         }
     }
 
-    fn init<T: Into<String>>(path: T, globals: BTreeMap<String, serde_json::Value>) {
+    fn init<T: Into<Source>>(source: T, globals: BTreeMap<String, serde_json::Value>) {
         let is_some = HANDLEBARS.read().unwrap().is_some();
-        let path: String = path.into();
         if !is_some {
             let mut engine = handlebars::Handlebars::new();
-            match engine
-                .register_templates_directory(".hbs", &format!("{}/", path.norm_strip_slashes()))
-            {
+            let result = match source.into() {
+                Source::Disk(path) => engine
+                    .register_templates_directory(".hbs", format!("{}/", path.norm_strip_slashes()))
+                    .map_err(|err| err.to_string()),
+                Source::Embedded(embedded) => embedded
+                    .iter()
+                    .filter_map(|file| {
+                        Some((
+                            file.path.strip_suffix(".hbs")?,
+                            std::str::from_utf8(file.data).ok()?,
+                        ))
+                    })
+                    .try_for_each(|(name, template)| {
+                        engine
+                            .register_template_string(name, template)
+                            .map_err(|err| err.to_string())
+                    }),
+            };
+
+            match result {
                 Ok(_) => *HANDLEBARS.write().unwrap() = Some((engine, globals)),
                 Err(err) => panic!("Failed to initialize Handlebars templating engine: {}", err),
             }
@@ -61,16 +78,14 @@ impl TemplateEngine for Handlebars {
                 .render(
                     &path,
                     &serde_json::to_value(context).map_err(|err| {
-                        (
-                            500,
-                            format!("Failed to convert Handlebars context to json: {}", err),
-                        )
+                        Error::new(500, "Failed to convert Handlebars context to json")
+                            .with_source(err)
                     })?,
                 )
-                .map_err(|err| (500, err.to_string())),
-            None => Err((
+                .map_err(Error::from),
+            None => Err(Error::new(
                 500,
-                "Handlebars templating engine is not active".to_string(),
+                "Handlebars templating engine is not active",
             )),
         }
     }
@@ -1,13 +1,16 @@
 pub mod hbs;
 pub mod ttera;
-use std::{collections::BTreeMap, marker::PhantomData};
+use std::{collections::BTreeMap, ffi::OsStr, marker::PhantomData, path::Path};
 
 #[cfg(feature = "handlebars")]
 pub use hbs::Handlebars;
 #[cfg(feature = "tera")]
 pub use ttera::Tera;
 
-use super::{Result, ToErrorResponse, ToResponse};
+use hyper::header::HeaderValue;
+
+use super::{full, Error, Response, Result, ToErrorResponse, ToResponse};
+use crate::assets::Source;
 
 #[macro_export]
 macro_rules! context {
@@ -39,7 +42,7 @@ macro_rules! template {
 pub trait TemplateEngine {
     fn parse_path(path: &str) -> String;
     fn context() -> BTreeMap<String, serde_json::Value>;
-    fn init<T: Into<String>>(path: T, globals: BTreeMap<String, serde_json::Value>);
+    fn init<T: Into<Source>>(source: T, globals: BTreeMap<String, serde_json::Value>);
     fn globals() -> BTreeMap<String, serde_json::Value>;
     fn render(path: String, context: BTreeMap<String, serde_json::Value>) -> Result<String>;
 }
@@ -49,20 +52,92 @@ pub trait TreeToTemplateContext {
     fn to_context(value: BTreeMap<String, serde_json::Value>) -> Self::Return;
 }
 
+/// Extensions of template files rather than of the rendered output
+const ENGINE_EXTENSIONS: [&str; 5] = ["hbs", "handlebars", "tera", "j2", "jinja"];
+
+/// Content type of a rendered template, ex: `text/html` for `index.html` and `feed.xml.hbs`.
+///
+/// Templates without a known output extension are sent as html.
+fn content_type(path: &str) -> String {
+    let mut path = Path::new(path);
+    while let Some(extension) = path.extension().and_then(OsStr::to_str) {
+        if !ENGINE_EXTENSIONS.contains(&extension) {
+            if let Some(mime) = mime_guess::from_ext(extension).first() {
+                return mime.to_string();
+            }
+            break;
+        }
+        path = match path.file_stem() {
+            Some(stem) => Path::new(stem),
+            None => break,
+        };
+    }
+    "text/html".to_string()
+}
+
+/// Response rendered from a template with the active [`TemplateEngine`]
+///
+/// The content type is inferred from the template's extension, ignoring the extension of the
+/// engine. When used as an error response the error's status is kept. Use
+/// [`ResponseExt`][super::ResponseExt] to send a different status.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "handlebars")]
+/// # mod example {
+/// use tela::{prelude::*, response::{template::Handlebars, Template}, StatusCode};
+///
+/// #[get("/sitemap.xml")]
+/// fn sitemap() -> Template<Handlebars> {
+///     // Sent as `text/xml`
+///     Template::new("sitemap.xml.hbs", context! { pages: ["/", "/about"] })
+/// }
+///
+/// #[post("/posts")]
+/// fn create() -> impl ToResponse {
+///     Template::<Handlebars>::new("post.hbs", context! { title: "Hello" })
+///         .status(StatusCode::Created)
+/// }
+/// # }
+/// ```
 pub struct Template<ENGINE: TemplateEngine>(
     pub String,
     pub BTreeMap<String, serde_json::Value>,
+    Option<String>,
     PhantomData<ENGINE>,
 );
 
 impl<ENGINE: TemplateEngine> Template<ENGINE> {
     pub fn new<T: Into<String>>(path: T, context: BTreeMap<String, serde_json::Value>) -> Self {
-        Template(path.into(), context, PhantomData)
+        Template(path.into(), context, None, PhantomData)
+    }
+
+    /// Content type to send instead of the one inferred from the template's extension
+    pub fn content_type<T: Into<String>>(mut self, content_type: T) -> Self {
+        self.2 = Some(content_type.into());
+        self
     }
 
     pub fn render(self) -> Result<String> {
         ENGINE::render(ENGINE::parse_path(&self.0), self.1)
     }
+
+    fn response(self, code: u16) -> Result<Response> {
+        let content_type = self.2.clone().unwrap_or_else(|| content_type(&self.0));
+        let content_type = HeaderValue::from_str(&content_type).map_err(|err| {
+            Error::new(
+                500,
+                format!("Invalid template content type {}", content_type),
+            )
+            .with_source(err)
+        })?;
+        let text = self.render()?;
+        hyper::Response::builder()
+            .status(code)
+            .header("Content-Type", content_type)
+            .body(full(text))
+            .map_err(|err| Error::new(500, "Failed to build template response").with_source(err))
+    }
 }
 
 impl<T: TemplateEngine> ToResponse for Template<T> {
@@ -70,29 +145,20 @@ impl<T: TemplateEngine> ToResponse for Template<T> {
         self,
         _method: &hyper::Method,
         _uri: &hyper::Uri,
+        _headers: &hyper::HeaderMap,
         _body: String,
-    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
-        self.render().map(|text| {
-            hyper::Response::builder()
-                .status(200)
-                .body(http_body_util::Full::new(bytes::Bytes::from(text)))
-                .unwrap()
-        })
+    ) -> Result<Response> {
+        self.response(200)
     }
 }
 
 impl<T: TemplateEngine> ToErrorResponse for Template<T> {
-    fn to_error_response(
-        self,
-        _code: u16,
-        _reason: String,
-    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
-        self.render().map(|text| {
-            hyper::Response::builder()
-                .status(200)
-                .body(http_body_util::Full::new(bytes::Bytes::from(text)))
-                .unwrap()
-        })
+    fn to_error_response(self, code: u16, reason: String) -> Result<Response> {
+        let mut response = self.response(code)?;
+        if let Ok(reason) = reason.parse() {
+            response.headers_mut().insert("Tela-Reason", reason);
+        }
+        Ok(response)
     }
 }
 
@@ -105,3 +171,17 @@ pub fn extend_context<const SIZE: usize>(
     map.append(&mut BTreeMap::from(values));
     map
 }
+
+#[cfg(test)]
+mod tests {
+    use super::content_type;
+
+    #[test]
+    fn content_type_from_extension() {
+        assert_eq!(content_type("index.html"), "text/html");
+        assert_eq!(content_type("feed.xml.hbs"), "text/xml");
+        assert_eq!(content_type("styles/site.css.tera"), "text/css");
+        assert_eq!(content_type("post.hbs"), "text/html");
+        assert_eq!(content_type("README"), "text/html");
+    }
+}
@@ -5,9 +5,10 @@ cfg_if::cfg_if! {
 use std::{collections::BTreeMap, sync::RwLock};
 
 use lazy_static::lazy_static;
-use crate::StripPath;
+use crate::{assets::Source, StripPath};
 
-use super::{TemplateEngine, TreeToTemplateContext, Result};
+use super::{Result, TemplateEngine, TreeToTemplateContext};
+use crate::response::Error;
 
 lazy_static! {
     pub static ref TERA: RwLock<Option<(tera::Tera, BTreeMap<String, serde_json::Value>)>> =
@@ -24,11 +25,24 @@ impl TemplateEngine for Tera {
         path.to_string()
     }
 
-    fn init<T: Into<String>>(path: T, globals: BTreeMap<String, serde_json::Value>) {
+    fn init<T: Into<Source>>(source: T, globals: BTreeMap<String, serde_json::Value>) {
         let is_some = TERA.read().unwrap().is_some();
-        let path: String = path.into();
         if !is_some {
-            match tera::Tera::new(&format!("{}/**/*", path.norm_strip_slashes())) {
+            let engine = match source.into() {
+                Source::Disk(path) => {
+                    tera::Tera::new(&format!("{}/**/*", path.norm_strip_slashes()))
+                }
+                Source::Embedded(embedded) => {
+                    let mut engine = tera::Tera::default();
+                    engine
+                        .add_raw_templates(embedded.iter().filter_map(|file| {
+                            Some((file.path, std::str::from_utf8(file.data).ok()?))
+                        }))
+                        .map(|_| engine)
+                }
+            };
+
+            match engine {
                 Ok(t) => *TERA.write().unwrap() = Some((t, globals)),
                 Err(err) => panic!("Failed to initialize Tera templating engine: {}", err),
             }
@@ -49,8 +63,8 @@ impl TemplateEngine for Tera {
             Some(tera) => tera
                 .0
                 .render(&path, &Tera::to_context(context))
-                .map_err(|err| (500, err.to_string())),
-            None => Err((500, "Tera templating engine is not active".to_string())),
+                .map_err(Error::from),
+            None => Err(Error::new(500, "Tera templating engine is not active")),
         }
     }
 }
@@ -1,19 +1,31 @@
-use std::{collections::HashMap, convert::Infallible, ffi::OsStr, fs, path::Path, sync::Arc};
+use std::{collections::HashMap, convert::Infallible, sync::Arc};
 
-use bytes::Bytes;
-use http_body_util::{BodyExt, Full};
-use hyper::{Method, Uri};
+use futures_util::StreamExt;
+use hyper::{http::Extensions, HeaderMap, Method, Uri};
 use tokio::sync::{
     mpsc::{self, Sender},
     oneshot,
 };
 
 use crate::{
-    errors::{default_error_page, StatusCode},
-    request::{Catch, Endpoint},
-    uri::index,
+    assets::{Assets, Lookup},
+    compression::{Compression, Decompression},
+    encoding::negotiate,
+    errors::{error_page, Details, RouteInfo, StatusCode},
+    panic::{catch_panic, Panic, PanicHook},
+    request::{BodyStream, BodyUsage, Catch, Endpoint, PendingBody},
+    response::{full, Error, Problem, Response, Result, ToErrorResponse, WithReason},
+    uri::{index, props},
 };
 
+/// Formats of unhandled error responses, in order of preference
+const ERROR_FORMATS: [&str; 4] = [
+    "text/html",
+    "application/problem+json",
+    "application/json",
+    "text/plain",
+];
+
 /// Commands sent through channel to router
 #[derive(Debug)]
 pub enum Command {
@@ -24,6 +36,7 @@ pub enum Command {
     },
     Error {
         code: u16,
+        path: String,
         response: oneshot::Sender<Option<ErrorHandler>>,
     },
 }
@@ -34,33 +47,157 @@ pub struct Route(pub Arc<dyn Endpoint>);
 #[derive(Debug, Clone)]
 pub struct ErrorHandler(pub Arc<dyn Catch>);
 
+impl ErrorHandler {
+    /// How specific the handler is for the error of a request to `path`, or `None` if it doesn't
+    /// handle the error. Handlers scoped to longer prefixes come first, then the ones with the
+    /// narrowest range of codes.
+    fn specificity(&self, code: u16, path: &str) -> Option<(usize, u16)> {
+        let prefix = match self.0.prefix() {
+            Some(prefix) => {
+                let prefix = prefix.trim_matches('/');
+                let scoped = prefix.is_empty()
+                    || path.trim_start_matches('/') == prefix
+                    || path
+                        .trim_start_matches('/')
+                        .strip_prefix(prefix)
+                        .map(|rest| rest.starts_with('/'))
+                        .unwrap_or(false);
+                if !scoped {
+                    return None;
+                }
+                prefix.len() + 1
+            }
+            None => 0,
+        };
+
+        let codes = self.0.codes();
+        if codes.is_empty() {
+            return Some((prefix, 0));
+        }
+        codes
+            .iter()
+            .filter(|range| range.contains(&code))
+            .map(|range| (prefix, u16::MAX - (range.end() - range.start())))
+            .max()
+    }
+}
+
 #[derive(Clone)]
 pub struct Router {
     channel: Option<Sender<Command>>,
     router: HashMap<Method, Vec<Route>>,
-    catch: HashMap<u16, ErrorHandler>,
-    assets: String,
+    catch: Vec<ErrorHandler>,
+    assets: Vec<Assets>,
+    compression: Option<Compression>,
+    decompression: Decompression,
+    body_limit: usize,
+    problem_details: bool,
+    on_panic: Option<PanicHook>,
 }
 impl Router {
     pub fn new() -> Self {
         Router {
             channel: None,
             router: HashMap::new(),
-            catch: HashMap::new(),
-            assets: "assets/".to_string(),
+            catch: Vec::new(),
+            assets: vec![Assets::new("assets/")],
+            compression: None,
+            decompression: Decompression::new(),
+            body_limit: 10 * 1024 * 1024,
+            problem_details: false,
+            on_panic: None,
         }
     }
 
-    pub fn assets(&mut self, path: String) {
-        self.assets = path;
+    /// Mount static assets. Assets mounted at the same prefix replace the previous mount.
+    pub fn assets(&mut self, assets: Assets) {
+        self.assets
+            .retain(|mount| mount.get_prefix() != assets.get_prefix());
+        self.assets.push(assets);
+        // Most specific prefixes are checked first
+        self.assets
+            .sort_by_key(|mount| std::cmp::Reverse(mount.get_prefix().len()));
     }
 
-    pub fn catch(&mut self, catch: Arc<dyn Catch>) {
-        if !self.catch.contains_key(&catch.code()) {
-            self.catch.insert(catch.code(), ErrorHandler(catch));
+    pub fn compression(&mut self, compression: Compression) {
+        self.compression = Some(compression);
+    }
+
+    pub fn decompression(&mut self, decompression: Decompression) {
+        self.decompression = decompression;
+    }
+
+    pub fn body_limit(&mut self, bytes: usize) {
+        self.body_limit = bytes;
+    }
+
+    pub fn problem_details(&mut self, enabled: bool) {
+        self.problem_details = enabled;
+    }
+
+    pub fn on_panic(&mut self, hook: PanicHook) {
+        self.on_panic = Some(hook);
+    }
+
+    /// Report a caught panic and turn it into a `500` error
+    fn panicked(&self, panic: Box<Panic>) -> Error {
+        if let Some(hook) = &self.on_panic {
+            hook(&panic);
+        }
+        (*panic).into()
+    }
+
+    /// Execute the endpoint and catch any panic
+    ///
+    /// Streamed bodies are read by blocking until the connection delivers more of the body, and
+    /// the connection is driven by the task calling this. Those endpoints run on the blocking
+    /// thread pool so the connection keeps reading while they wait.
+    async fn execute(
+        endpoint: &Arc<dyn Endpoint>,
+        method: &Method,
+        uri: &mut Uri,
+        headers: &HeaderMap,
+        body: &mut Vec<u8>,
+        mut extensions: Extensions,
+    ) -> std::result::Result<Result<Response>, Box<Panic>> {
+        if endpoint.body_usage() != BodyUsage::Streamed {
+            return catch_panic(method, &uri.clone(), || {
+                endpoint.execute(method, uri, headers, body, &mut extensions)
+            });
+        }
+
+        let endpoint = endpoint.clone();
+        let (method, headers) = (method.clone(), headers.clone());
+        let (mut moved_uri, mut moved_body) = (uri.clone(), std::mem::take(body));
+        let task = tokio::task::spawn_blocking(move || {
+            let result = catch_panic(&method, &moved_uri.clone(), || {
+                endpoint.execute(
+                    &method,
+                    &mut moved_uri,
+                    &headers,
+                    &mut moved_body,
+                    &mut extensions,
+                )
+            });
+            (result, moved_uri, moved_body)
+        });
+
+        match task.await {
+            Ok((result, moved_uri, moved_body)) => {
+                *uri = moved_uri;
+                *body = moved_body;
+                result
+            }
+            Err(error) => Ok(Err(
+                Error::new(500, "Endpoint task was cancelled").with_source(error)
+            )),
         }
     }
 
+    pub fn catch(&mut self, catch: Arc<dyn Catch>) {
+        self.catch.push(ErrorHandler(catch));
+    }
+
     pub fn route(&mut self, route: Arc<dyn Endpoint>) {
         for method in route.methods() {
             if !self.router.contains_key(&method) {
@@ -109,16 +246,23 @@ impl Router {
                         };
                         response.send(None).unwrap();
                     }
-                    Error { code, response } => {
-                        if catch.contains_key(&code) {
-                            response
-                                .send(catch.get(&code).map(|eh| eh.clone()))
-                                .unwrap()
-                        } else if catch.contains_key(&0) {
-                            response.send(catch.get(&0).map(|eh| eh.clone())).unwrap()
-                        } else {
-                            response.send(None).unwrap()
+                    Error {
+                        code,
+                        path,
+                        response,
+                    } => {
+                        // The first handler added wins when two are equally specific
+                        let mut best: Option<(&ErrorHandler, (usize, u16))> = None;
+                        for handler in catch.iter() {
+                            if let Some(specificity) = handler.specificity(code, &path) {
+                                if best.map(|(_, b)| specificity > b).unwrap_or(true) {
+                                    best = Some((handler, specificity));
+                                }
+                            }
                         }
+                        response
+                            .send(best.map(|(handler, _)| handler.clone()))
+                            .unwrap()
                     }
                 }
             }
@@ -127,19 +271,23 @@ impl Router {
         self.channel = Some(tx);
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn error(
         &self,
         uri: &Uri,
         method: &Method,
+        headers: &HeaderMap,
         body: &Vec<u8>,
-        code: u16,
-        reason: String,
+        error: Error,
+        endpoint: Option<&Arc<dyn Endpoint>>,
         channel: Sender<Command>,
-    ) -> std::result::Result<hyper::Response<Full<Bytes>>, Infallible> {
+    ) -> std::result::Result<Response, Infallible> {
+        let code = error.get_status();
         let (error_tx, error_rx) = oneshot::channel();
         match channel
             .send(Command::Error {
-                code: code.clone(),
+                code,
+                path: uri.path().to_string(),
                 response: error_tx,
             })
             .await
@@ -148,44 +296,78 @@ impl Router {
             Err(error) => eprintln!("{:?}", error),
         };
 
-        match error_rx.await.unwrap() {
+        // Unhandled errors are rendered in the format the client prefers
+        let format = match self.problem_details {
+            true => Some("application/problem+json"),
+            false => negotiate(headers, &ERROR_FORMATS),
+        };
+        let problem = matches!(
+            format,
+            Some("application/problem+json" | "application/json")
+        );
+        let page = |error: Error| {
+            let mut response = match format {
+                Some("application/problem+json" | "application/json") => Problem::from(&error)
+                    .instance(uri.path())
+                    .to_error_response(error.get_status(), error.reason())
+                    .unwrap(),
+                Some("text/plain") => hyper::Response::builder()
+                    .status(error.get_status())
+                    .header("Content-Type", "text/plain")
+                    .tela_reason(&error.reason())
+                    .body(full(format!(
+                        "{} {}: {}",
+                        error.get_status(),
+                        StatusCode::from(error.get_status()).message(),
+                        error.reason()
+                    )))
+                    .unwrap(),
+                _ => error_page(
+                    &error.get_status(),
+                    &error.reason(),
+                    method,
+                    uri,
+                    std::str::from_utf8(body).unwrap_or("").to_string(),
+                    Details {
+                        headers: Some(headers),
+                        route: endpoint.map(|endpoint| RouteInfo {
+                            pattern: endpoint.path(),
+                            handler: format!("{:?}", endpoint),
+                            captures: props(&uri.path().to_string(), &endpoint.path()),
+                        }),
+                        backtrace: error.backtrace(),
+                    },
+                ),
+            };
+            error.apply(&mut response);
+            response
+        };
+
+        let response = match error_rx.await.unwrap() {
             Some(ErrorHandler(handler)) => {
-                match handler.execute(
-                    code.clone(),
-                    StatusCode::from(code.clone()).message(),
-                    reason.clone(),
-                ) {
-                    Ok(response) => {
-                        Router::log_request(
-                            &uri.path().to_string(),
-                            &method.clone(),
-                            &response.status().into(),
-                        );
-                        Ok(response)
-                    }
-                    Err((code, reason)) => {
-                        Router::log_request(&uri.path().to_string(), method, &code);
-                        Ok(default_error_page(
-                            &code,
-                            &reason,
-                            method,
-                            uri,
-                            std::str::from_utf8(body).unwrap_or("").to_string(),
-                        ))
+                let extra = error.get_headers().clone();
+                let result = catch_panic(method, uri, || {
+                    handler.execute(method, uri, headers, body, error)
+                });
+                match result.unwrap_or_else(|panic| Err(self.panicked(panic))) {
+                    Ok(mut response) => {
+                        response.headers_mut().extend(extra);
+                        response
                     }
+                    Err(error) => page(error),
                 }
             }
-            None => {
-                Router::log_request(&uri.path().to_string(), method, &code);
-                Ok(default_error_page(
-                    &code,
-                    &reason,
-                    method,
-                    uri,
-                    std::str::from_utf8(body).unwrap_or("").to_string(),
-                ))
+            None if !problem && error.is_structured() => {
+                let status = error.get_status();
+                error
+                    .to_error_response(status, String::new())
+                    .unwrap_or_else(page)
             }
-        }
+            None => page(error),
+        };
+
+        Router::log_request(&uri.path().to_string(), method, &response.status().into());
+        Ok(response)
     }
 
     fn log_request(path: &String, method: &Method, status: &u16) {
@@ -209,49 +391,62 @@ impl Router {
     pub async fn parse(
         &self,
         request: hyper::Request<hyper::body::Incoming>,
-    ) -> Result<hyper::Response<Full<Bytes>>, Infallible> {
+    ) -> Result<Response, Infallible> {
+        let headers = request.headers().clone();
+        let response = self.handle(request).await?;
+
+        match &self.compression {
+            Some(compression) => Ok(compression.compress(&headers, response).await),
+            None => Ok(response),
+        }
+    }
+
+    async fn handle(
+        &self,
+        request: hyper::Request<hyper::body::Incoming>,
+    ) -> Result<Response, Infallible> {
         // Get all needed information from request
         let mut uri = request.uri().clone();
         let method = request.method().clone();
         // Can be used for validation, authentication, and other features
-        let _headers = request.headers().clone();
-        let mut body = request.collect().await.unwrap().to_bytes().to_vec();
+        let mut headers = request.headers().clone();
+        let (mut parts, incoming) = request.into_parts();
+        // The body is only read once the endpoint that handles the request is known
+        let mut body = Vec::new();
 
         let (endpoint_tx, endpoint_rx) = oneshot::channel();
         match &self.channel {
             Some(channel) => {
-                let path = format!("{}{}", self.assets, uri.path());
-                let path = Path::new(&path);
-                if let Some(extension) = path.extension().and_then(OsStr::to_str) {
-                    match fs::read_to_string(path) {
-                        Ok(text) => {
-                            Router::log_request(&uri.path().to_string(), &method, &200);
-                            let mut builder = hyper::Response::builder().status(200);
-
-                            match mime_guess::from_ext(extension).first() {
-                                Some(mime) => {
-                                    builder = builder.header("Content-Type", mime.to_string())
-                                }
-                                _ => {}
-                            };
-
-                            return Ok(builder.body(Full::new(Bytes::from(text))).unwrap());
-                        }
-                        _ => {
-                            Router::log_request(&uri.path().to_string(), &method, &404);
-                            return Ok(default_error_page(
-                                &404,
-                                &"File not found".to_string(),
+                let mut missing = false;
+                for assets in self.assets.iter() {
+                    match assets.file(&uri, &headers) {
+                        Lookup::Found(response) => {
+                            Router::log_request(
+                                &uri.path().to_string(),
                                 &method,
-                                &uri,
-                                std::str::from_utf8(body.as_slice())
-                                    .unwrap_or("")
-                                    .to_string(),
-                            ));
+                                &response.status().into(),
+                            );
+                            return Ok(response);
                         }
+                        Lookup::Missing => missing = true,
+                        Lookup::Pass => {}
                     }
                 }
 
+                if missing {
+                    return self
+                        .error(
+                            &uri,
+                            &method,
+                            &headers,
+                            &body,
+                            Error::new(404, "File not found"),
+                            None,
+                            channel.clone(),
+                        )
+                        .await;
+                }
+
                 match channel
                     .send(Command::Get {
                         method: method.clone(),
@@ -265,27 +460,147 @@ impl Router {
                 };
 
                 match endpoint_rx.await.unwrap() {
-                    Some(Route(endpoint)) => match endpoint.execute(&method, &mut uri, &mut body) {
-                        Ok(response) => {
+                    Some(Route(endpoint)) => {
+                        let usage = endpoint.body_usage();
+                        let limit = endpoint.body_limit().unwrap_or(self.body_limit);
+                        let length = headers
+                            .get("Content-Length")
+                            .and_then(|length| length.to_str().ok())
+                            .and_then(|length| length.parse::<usize>().ok());
+
+                        if usage != BodyUsage::Ignored && length.unwrap_or(0) > limit {
+                            return self
+                                .error(
+                                    &uri,
+                                    &method,
+                                    &headers,
+                                    &body,
+                                    Error::new(
+                                        413,
+                                        format!("Request body exceeds {} bytes", limit),
+                                    ),
+                                    Some(&endpoint),
+                                    channel.clone(),
+                                )
+                                .await;
+                        }
+
+                        let mut stream = BodyStream::new(incoming, limit);
+                        match usage {
+                            BodyUsage::Ignored => {}
+                            BodyUsage::Streamed => {
+                                if let Err(error) = self.decompression.check_streamed(&headers) {
+                                    return self
+                                        .error(
+                                            &uri,
+                                            &method,
+                                            &headers,
+                                            &body,
+                                            error,
+                                            Some(&endpoint),
+                                            channel.clone(),
+                                        )
+                                        .await;
+                                }
+                                parts.extensions.insert(PendingBody::new(stream));
+                            }
+                            BodyUsage::Buffered => {
+                                while let Some(chunk) = stream.next().await {
+                                    match chunk {
+                                        Ok(chunk) => body.extend_from_slice(&chunk),
+                                        Err(error) => {
+                                            return self
+                                                .error(
+                                                    &uri,
+                                                    &method,
+                                                    &headers,
+                                                    &body,
+                                                    error,
+                                                    Some(&endpoint),
+                                                    channel.clone(),
+                                                )
+                                                .await
+                                        }
+                                    }
+                                }
+
+                                if let Err(error) =
+                                    self.decompression
+                                        .decompress(&mut headers, &mut body, limit)
+                                {
+                                    return self
+                                        .error(
+                                            &uri,
+                                            &method,
+                                            &headers,
+                                            &body,
+                                            error,
+                                            Some(&endpoint),
+                                            channel.clone(),
+                                        )
+                                        .await;
+                                }
+                            }
+                        }
+
+                        let result = Router::execute(
+                            &endpoint,
+                            &method,
+                            &mut uri,
+                            &headers,
+                            &mut body,
+                            std::mem::take(&mut parts.extensions),
+                        )
+                        .await;
+                        match result.unwrap_or_else(|panic| Err(self.panicked(panic))) {
+                            Ok(response) => {
+                                Router::log_request(
+                                    &uri.path().to_string(),
+                                    &method,
+                                    &response.status().into(),
+                                );
+                                Ok(response)
+                            }
+                            Err(error) => {
+                                self.error(
+                                    &uri,
+                                    &method,
+                                    &headers,
+                                    &body,
+                                    error,
+                                    Some(&endpoint),
+                                    channel.clone(),
+                                )
+                                .await
+                            }
+                        }
+                    }
+                    None => {
+                        let fallback = self
+                            .assets
+                            .iter()
+                            .find_map(|assets| assets.directory(&uri, &headers))
+                            .or_else(|| {
+                                self.assets
+                                    .iter()
+                                    .find_map(|assets| assets.fallback_for(&method, &uri, &headers))
+                            });
+                        if let Some(response) = fallback {
                             Router::log_request(
                                 &uri.path().to_string(),
                                 &method,
                                 &response.status().into(),
                             );
-                            Ok(response)
-                        }
-                        Err((code, reason)) => {
-                            self.error(&uri, &method, &body, code, reason, channel.clone())
-                                .await
+                            return Ok(response);
                         }
-                    },
-                    None => {
+
                         self.error(
                             &uri,
                             &method,
+                            &headers,
                             &body,
-                            404,
-                            "Page not found in router".to_string(),
+                            Error::new(404, "Page not found in router"),
+                            None,
                             channel.clone(),
                         )
                         .await
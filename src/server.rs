@@ -5,6 +5,9 @@ use hyper::{server::conn::http1, service::service_fn};
 use tokio::net::TcpListener;
 
 use crate::{
+    assets::Assets,
+    compression::{Compression, Decompression},
+    panic::Panic,
     prelude::{Catch, Endpoint},
     support::TokioIo,
     Router,
@@ -57,11 +60,11 @@ pub struct Server {
 
 #[cfg(feature = "handlebars")]
 impl Server {
-    /// Setup the tera template root path
+    /// Setup the handlebars template root path
     ///
-    /// This exposes all files in that path to the tera templating
-    /// engine.
-    pub fn handlebars<T: Into<String>>(
+    /// This exposes all `.hbs` files in that path, or embedded directory, to the
+    /// handlebars templating engine.
+    pub fn handlebars<T: Into<crate::assets::Source>>(
         self,
         path: T,
         globals: std::collections::BTreeMap<String, serde_json::Value>,
@@ -75,9 +78,9 @@ impl Server {
 impl Server {
     /// Setup the tera template root path
     ///
-    /// This exposes all files in that path to the tera templating
-    /// engine.
-    pub fn tera<T: Into<String>>(
+    /// This exposes all files in that path, or embedded directory, to the tera
+    /// templating engine.
+    pub fn tera<T: Into<crate::assets::Source>>(
         self,
         path: T,
         globals: std::collections::BTreeMap<String, serde_json::Value>,
@@ -95,8 +98,138 @@ impl Server {
     }
 
     /// Set where static files should be served from
-    pub fn assets<T: Into<String>>(mut self, path: T) -> Self {
-        self.router.assets(Into::<String>::into(path));
+    ///
+    /// A path mounts the files at the uri root, replacing the default `assets/` directory.
+    /// An `Assets` mount can be given instead to configure the prefix, index files, directory
+    /// listings, and fallback file. Call this multiple times to mount multiple directories.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::{assets::Assets, Server};
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .assets("public/")
+    ///         .assets(Assets::new("app/dist/").prefix("/app").fallback("index.html"))
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    pub fn assets<T: Into<Assets>>(mut self, assets: T) -> Self {
+        self.router.assets(assets.into());
+        self
+    }
+
+    /// Compress responses based on the request's `Accept-Encoding`
+    ///
+    /// Each encoding is enabled with a feature of the same name: `brotli`, `zstd`, `gzip`,
+    /// and `deflate`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::{compression::Compression, Server};
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .compression(Compression::new().threshold(512))
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.router.compression(compression);
+        self
+    }
+
+    /// Configure how compressed request bodies are decompressed
+    ///
+    /// Request bodies are decompressed according to their `Content-Encoding` before they are
+    /// given to endpoints. By default bodies may decompress up to the route's body limit.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::{compression::Decompression, Server};
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .decompression(Decompression::new().limit(1024 * 1024))
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    pub fn decompression(mut self, decompression: Decompression) -> Self {
+        self.router.decompression(decompression);
+        self
+    }
+
+    /// Max size, in bytes, of a request body. Defaults to 10MB.
+    ///
+    /// Endpoints can override the limit with the `limit` argument, ex:
+    /// `#[post("/upload", limit = "50MB")]`. Larger bodies are rejected with
+    /// `413 Payload Too Large`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::Server;
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .body_limit(2 * 1024 * 1024)
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    pub fn body_limit(mut self, bytes: usize) -> Self {
+        self.router.body_limit(bytes);
+        self
+    }
+
+    /// Respond to every unhandled error with an RFC 7807 `application/problem+json` body.
+    ///
+    /// Without this option problems are only used when the client's `Accept` header prefers
+    /// json; other clients get the default error page. Errors with a catch handler are unchanged.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::Server;
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .problem_details(true)
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    pub fn problem_details(mut self, enabled: bool) -> Self {
+        self.router.problem_details(enabled);
+        self
+    }
+
+    /// Called with every panic caught while handling a request, ex: to report it.
+    ///
+    /// Panics in endpoints and catch handlers never drop the connection. They are answered as a
+    /// `500 Internal Server Error` through the catch handlers, and the debug error page shows the
+    /// panic message and location.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::Server;
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .on_panic(|panic| eprintln!("{} {}", panic.uri(), panic))
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    pub fn on_panic<F: Fn(&Panic) + Send + Sync + 'static>(mut self, hook: F) -> Self {
+        self.router.on_panic(Arc::new(hook));
         self
     }
 
@@ -238,6 +371,7 @@ impl Server {
             tokio::task::spawn(async move {
                 if let Err(err) = http1::Builder::new()
                     .serve_connection(io, service_fn(|req| rh.parse(req)))
+                    .with_upgrades()
                     .await
                 {
                     println!("Error serving connection: {:?}", err);
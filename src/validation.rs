@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+
+use hyper::{HeaderMap, Method, Uri};
+use serde::Serialize;
+
+use crate::{
+    encoding::prefers_json,
+    request::{Body, Form, IntoBody, IntoQuery, Query, RequestData, ToParam},
+    response::{full, Error, Response, Result, ToErrorResponse, ToResponse},
+};
+
+pub use tela_macros::Validate;
+
+/// Check that a value follows its rules
+///
+/// Usually derived with `#[derive(Validate)]`. Each field can have any number of rules:
+///
+/// - `length(min = 1, max = 20)`: number of characters in a string or items in a `Vec`
+/// - `range(min = 0, max = 150)`: inclusive bounds of a number
+/// - `email`: a valid email address
+/// - `regex = "^[a-z]+$"`: the string must match the pattern
+/// - `custom = "path::to::function"`: a `fn(&T) -> Result<(), String>` with the error message
+///
+/// Rules for `Option` fields are only checked when the value is `Some`.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+/// use tela::{prelude::*, request::Body, validation::{Valid, Validate}};
+///
+/// #[derive(Deserialize, Validate)]
+/// struct Signup {
+///     #[validate(length(min = 3, max = 20), regex = "^[a-z0-9_]+$")]
+///     username: String,
+///     #[validate(email)]
+///     email: String,
+///     #[validate(range(min = 13, max = 150))]
+///     age: u8,
+/// }
+///
+/// #[post("/signup")]
+/// fn signup(Valid(Body(form)): Valid<Body<Signup>>) -> String {
+///     format!("Welcome {}", form.username)
+/// }
+/// ```
+pub trait Validate {
+    fn validate(&self) -> std::result::Result<(), ValidationErrors>;
+}
+
+/// Single rule a field failed
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    /// Name of the rule, ex: `length`
+    pub rule: String,
+    pub message: String,
+}
+
+/// Every rule that failed while validating a value
+///
+/// Responds with `422 Unprocessable Entity` listing every field error. The list is JSON when the
+/// client prefers `application/json` and HTML otherwise.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationErrors {
+    errors: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        ValidationErrors::default()
+    }
+
+    pub fn add<F: Into<String>, R: Into<String>, M: Into<String>>(
+        &mut self,
+        field: F,
+        rule: R,
+        message: M,
+    ) {
+        self.errors.push(FieldError {
+            field: field.into(),
+            rule: rule.into(),
+            message: message.into(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn errors(&self) -> &[FieldError] {
+        &self.errors
+    }
+
+    /// Messages grouped by the field they belong to
+    pub fn fields(&self) -> HashMap<&str, Vec<&str>> {
+        let mut fields: HashMap<&str, Vec<&str>> = HashMap::new();
+        for error in self.errors.iter() {
+            fields
+                .entry(error.field.as_str())
+                .or_default()
+                .push(error.message.as_str());
+        }
+        fields
+    }
+
+    /// `Ok` if no rules failed
+    pub fn into_result(self) -> std::result::Result<(), ValidationErrors> {
+        match self.errors.is_empty() {
+            true => Ok(()),
+            false => Err(self),
+        }
+    }
+
+    /// Errors as a single line, ex: `username: must be at least 3 characters long`
+    pub fn summary(&self) -> String {
+        self.errors
+            .iter()
+            .map(|error| format!("{}: {}", error.field, error.message))
+            .collect::<Vec<String>>()
+            .join("; ")
+    }
+
+    fn json(&self) -> String {
+        serde_json::json!({
+            "status": 422,
+            "message": "Validation failed",
+            "errors": self.errors,
+        })
+        .to_string()
+    }
+
+    fn html(&self) -> String {
+        let escape = |value: &str| {
+            value
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+        };
+        format!(
+            "<!DOCTYPE html><html><head><title>422 Unprocessable Entity</title></head><body><h1>Validation failed</h1><ul>{}</ul></body></html>",
+            self.errors
+                .iter()
+                .map(|error| format!(
+                    "<li><strong>{}</strong>: {}</li>",
+                    escape(&error.field),
+                    escape(&error.message)
+                ))
+                .collect::<String>()
+        )
+    }
+
+    fn response(self, code: u16, json: bool) -> Response {
+        let (content_type, body) = match json {
+            true => ("application/json", self.json()),
+            false => ("text/html", self.html()),
+        };
+        hyper::Response::builder()
+            .status(code)
+            .header("Content-Type", content_type)
+            .body(full(body))
+            .unwrap()
+    }
+}
+
+impl ToResponse for ValidationErrors {
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        headers: &HeaderMap,
+        _body: String,
+    ) -> Result<Response> {
+        let json = prefers_json(headers);
+        Ok(self.response(422, json))
+    }
+}
+
+impl ToErrorResponse for ValidationErrors {
+    fn to_error_response(self, code: u16, _reason: String) -> Result<Response> {
+        Ok(self.response(code, true))
+    }
+}
+
+/// Extracted value that can be validated
+pub trait Validated {
+    fn validated(&self) -> std::result::Result<(), ValidationErrors>;
+}
+
+impl<T: IntoBody + Validate> Validated for Body<T> {
+    fn validated(&self) -> std::result::Result<(), ValidationErrors> {
+        self.0.validate()
+    }
+}
+
+impl<T: IntoQuery + Validate> Validated for Query<T> {
+    fn validated(&self) -> std::result::Result<(), ValidationErrors> {
+        self.0.validate()
+    }
+}
+
+impl<T: Validate> Validated for Form<T> {
+    fn validated(&self) -> std::result::Result<(), ValidationErrors> {
+        self.0.validate()
+    }
+}
+
+/// Extractor that validates the wrapped `Body`, `Query`, or `Form` after it is extracted
+///
+/// When used directly as an endpoint parameter invalid values are answered with a `422` response
+/// listing every field error; see [`ValidationErrors`]. `Option<Valid<T>>` gives `None` instead.
+#[derive(Debug, Clone, Copy)]
+pub struct Valid<T>(pub T);
+
+impl<T: Validated> Valid<T> {
+    /// Validate an extracted value
+    pub fn check(value: T) -> std::result::Result<Valid<T>, ValidationErrors> {
+        value.validated().map(|_| Valid(value))
+    }
+}
+
+impl<T: Validated> ToParam<Valid<T>> for RequestData
+where
+    RequestData: ToParam<T>,
+{
+    fn to_param(&mut self) -> Result<Valid<T>> {
+        let value: T = self.to_param()?;
+        Valid::check(value).map_err(|errors| {
+            Error::new(422, errors.summary())
+                .code("validation")
+                .details(errors.errors())
+        })
+    }
+}
+
+impl<T: Validated> ToParam<Option<Valid<T>>> for RequestData
+where
+    RequestData: ToParam<T>,
+{
+    fn to_param(&mut self) -> Result<Option<Valid<T>>> {
+        let value: Result<T> = self.to_param();
+        Ok(value.ok().and_then(|value| Valid::check(value).ok()))
+    }
+}
+
+/// Number of characters or items checked by the `length` rule
+pub trait Length {
+    fn length(&self) -> usize;
+}
+
+impl Length for str {
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl Length for String {
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl<T> Length for Vec<T> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> Length for [T] {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: Length + ?Sized> Length for &T {
+    fn length(&self) -> usize {
+        (**self).length()
+    }
+}
+
+/// Check for a reasonable email address: `local@domain.tld`
+pub fn is_email(value: &str) -> bool {
+    let (local, domain) = match value.rsplit_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+
+    !local.is_empty()
+        && local.len() <= 64
+        && !local.chars().any(|c| c.is_whitespace() || c == '@')
+        && domain.len() <= 255
+        && domain.contains('.')
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_email;
+
+    #[test]
+    fn valid_emails() {
+        assert!(is_email("user@example.com"));
+        assert!(is_email("first.last+tag@mail.example.co.uk"));
+        assert!(is_email("user@my-domain.org"));
+    }
+
+    #[test]
+    fn invalid_emails() {
+        assert!(!is_email("user"));
+        assert!(!is_email("@example.com"));
+        assert!(!is_email("user@localhost"));
+        assert!(!is_email("user@example..com"));
+        assert!(!is_email("user@-example.com"));
+        assert!(!is_email("user@example-.com"));
+        assert!(!is_email("us er@example.com"));
+        assert!(!is_email("user@exa_mple.com"));
+        assert!(!is_email(&format!("{}@example.com", "a".repeat(65))));
+    }
+}
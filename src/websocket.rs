@@ -0,0 +1,320 @@
+use std::future::Future;
+
+use futures_util::{SinkExt, StreamExt};
+use hyper::{upgrade::OnUpgrade, HeaderMap, Method, Uri};
+use tokio_tungstenite::{
+    tungstenite::{
+        self,
+        handshake::derive_accept_key,
+        protocol::{frame::coding::CloseCode, Role, WebSocketConfig},
+    },
+    WebSocketStream,
+};
+
+use crate::{
+    request::{RequestData, ToParam},
+    response::{empty, Response, Result, ToResponse},
+    support::TokioIo,
+};
+
+/// Error while sending or receiving websocket messages
+pub use tungstenite::Error;
+
+/// Extractor for requests that want to upgrade to a websocket connection
+///
+/// Respond with [`WebSocketUpgrade::on_upgrade`] to complete the handshake and handle the
+/// connection. Requests that aren't a valid upgrade are rejected with `400 Bad Request`, or
+/// `426 Upgrade Required` for unsupported websocket versions. Take `Option<WebSocketUpgrade>` to
+/// handle them yourself.
+///
+/// Requires the `websocket` feature.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, websocket::{Message, WebSocketUpgrade, Upgrade}};
+///
+/// #[get("/echo")]
+/// fn echo(ws: WebSocketUpgrade) -> Upgrade {
+///     ws.protocols(["echo"]).on_upgrade(|mut socket| async move {
+///         while let Some(Ok(message)) = socket.recv().await {
+///             if let Message::Text(text) = message {
+///                 if socket.send(Message::Text(text)).await.is_err() {
+///                     break;
+///                 }
+///             }
+///         }
+///     })
+/// }
+/// ```
+pub struct WebSocketUpgrade {
+    key: String,
+    upgrade: OnUpgrade,
+    requested: Vec<String>,
+    protocol: Option<String>,
+    max_message_size: usize,
+}
+
+impl WebSocketUpgrade {
+    /// Subprotocols the endpoint supports. The first protocol the client requested that is also
+    /// supported is selected.
+    pub fn protocols<T: Into<String>, const SIZE: usize>(mut self, protocols: [T; SIZE]) -> Self {
+        let supported: Vec<String> = protocols.into_iter().map(|p| p.into()).collect();
+        self.protocol = self
+            .requested
+            .iter()
+            .find(|requested| supported.contains(requested))
+            .cloned();
+        self
+    }
+
+    /// Subprotocol that will be used for the connection
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// Max size, in bytes, of a single message. Defaults to 64MB.
+    pub fn max_message_size(mut self, bytes: usize) -> Self {
+        self.max_message_size = bytes;
+        self
+    }
+
+    /// Complete the handshake and call the callback with the connection once it is upgraded
+    pub fn on_upgrade<F, R>(self, callback: F) -> Upgrade
+    where
+        F: FnOnce(WebSocket) -> R + Send + 'static,
+        R: Future<Output = ()> + Send + 'static,
+    {
+        let accept = derive_accept_key(self.key.as_bytes());
+        let protocol = self.protocol.clone();
+        let upgrade = self.upgrade;
+
+        let config = WebSocketConfig {
+            max_message_size: Some(self.max_message_size),
+            max_frame_size: Some(self.max_message_size),
+            ..Default::default()
+        };
+
+        tokio::spawn(async move {
+            match upgrade.await {
+                Ok(upgraded) => {
+                    let stream = WebSocketStream::from_raw_socket(
+                        TokioIo::new(upgraded),
+                        Role::Server,
+                        Some(config),
+                    )
+                    .await;
+                    callback(WebSocket {
+                        stream,
+                        protocol: self.protocol,
+                    })
+                    .await
+                }
+                Err(err) => eprintln!("Failed to upgrade websocket connection: {}", err),
+            }
+        });
+
+        Upgrade { accept, protocol }
+    }
+}
+
+fn has_token(headers: &HeaderMap, name: &str, token: &str) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|value| value.trim().eq_ignore_ascii_case(token))
+}
+
+impl RequestData {
+    fn websocket_upgrade(&mut self) -> Result<WebSocketUpgrade> {
+        let headers = &self.3;
+        if self.1 != Method::GET
+            || !has_token(headers, "Connection", "upgrade")
+            || !has_token(headers, "Upgrade", "websocket")
+        {
+            return Err(crate::response::Error::new(
+                400,
+                "Expected a websocket upgrade request",
+            ));
+        }
+
+        if headers
+            .get("Sec-WebSocket-Version")
+            .and_then(|v| v.to_str().ok())
+            != Some("13")
+        {
+            return Err(crate::response::Error::new(
+                426,
+                "Unsupported Sec-WebSocket-Version; expected 13",
+            ));
+        }
+
+        let key = match headers
+            .get("Sec-WebSocket-Key")
+            .and_then(|k| k.to_str().ok())
+        {
+            Some(key) => key.trim().to_string(),
+            None => {
+                return Err(crate::response::Error::new(
+                    400,
+                    "Missing Sec-WebSocket-Key header",
+                ))
+            }
+        };
+
+        let requested = headers
+            .get_all("Sec-WebSocket-Protocol")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|protocol| protocol.trim().to_string())
+            .filter(|protocol| !protocol.is_empty())
+            .collect();
+
+        let upgrade = match self.4.remove::<OnUpgrade>() {
+            Some(upgrade) => upgrade,
+            None => {
+                return Err(crate::response::Error::new(
+                    500,
+                    "Connection can not be upgraded",
+                ))
+            }
+        };
+
+        Ok(WebSocketUpgrade {
+            key,
+            upgrade,
+            requested,
+            protocol: None,
+            max_message_size: 64 << 20,
+        })
+    }
+}
+
+impl ToParam<WebSocketUpgrade> for RequestData {
+    fn to_param(&mut self) -> Result<WebSocketUpgrade> {
+        self.websocket_upgrade()
+    }
+}
+
+impl ToParam<Option<WebSocketUpgrade>> for RequestData {
+    fn to_param(&mut self) -> Result<Option<WebSocketUpgrade>> {
+        Ok(self.websocket_upgrade().ok())
+    }
+}
+
+/// `101 Switching Protocols` response that completes the websocket handshake
+pub struct Upgrade {
+    accept: String,
+    protocol: Option<String>,
+}
+
+impl ToResponse for Upgrade {
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        _headers: &HeaderMap,
+        _body: String,
+    ) -> Result<Response> {
+        let mut builder = hyper::Response::builder()
+            .status(101)
+            .header("Connection", "Upgrade")
+            .header("Upgrade", "websocket")
+            .header("Sec-WebSocket-Accept", self.accept);
+        if let Some(protocol) = self.protocol {
+            builder = builder.header("Sec-WebSocket-Protocol", protocol);
+        }
+        Ok(builder.body(empty()).unwrap())
+    }
+}
+
+/// Websocket message
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    /// Close the connection with an optional code and reason
+    Close(Option<(u16, String)>),
+}
+
+impl From<Message> for tungstenite::Message {
+    fn from(message: Message) -> Self {
+        match message {
+            Message::Text(text) => tungstenite::Message::Text(text),
+            Message::Binary(data) => tungstenite::Message::Binary(data),
+            Message::Ping(data) => tungstenite::Message::Ping(data),
+            Message::Pong(data) => tungstenite::Message::Pong(data),
+            Message::Close(frame) => tungstenite::Message::Close(frame.map(|(code, reason)| {
+                tungstenite::protocol::CloseFrame {
+                    code: CloseCode::from(code),
+                    reason: reason.into(),
+                }
+            })),
+        }
+    }
+}
+
+/// Upgraded websocket connection
+pub struct WebSocket {
+    stream: WebSocketStream<TokioIo<hyper::upgrade::Upgraded>>,
+    protocol: Option<String>,
+}
+
+impl WebSocket {
+    /// Receive the next message. Returns `None` once the connection is closed.
+    ///
+    /// Pings are answered with a pong automatically but are still returned.
+    pub async fn recv(&mut self) -> Option<std::result::Result<Message, Error>> {
+        loop {
+            let message = match self.stream.next().await? {
+                Ok(message) => message,
+                Err(err) => return Some(Err(err)),
+            };
+
+            return Some(Ok(match message {
+                tungstenite::Message::Text(text) => Message::Text(text),
+                tungstenite::Message::Binary(data) => Message::Binary(data),
+                tungstenite::Message::Ping(data) => {
+                    // The pong is queued when the ping is read; send it right away
+                    if let Err(err) = self.stream.flush().await {
+                        return Some(Err(err));
+                    }
+                    Message::Ping(data)
+                }
+                tungstenite::Message::Pong(data) => Message::Pong(data),
+                tungstenite::Message::Close(frame) => Message::Close(
+                    frame.map(|frame| (u16::from(frame.code), frame.reason.into_owned())),
+                ),
+                // Raw frames are never returned while reading
+                tungstenite::Message::Frame(_) => continue,
+            }));
+        }
+    }
+
+    pub async fn send(&mut self, message: Message) -> std::result::Result<(), Error> {
+        self.stream.send(message.into()).await
+    }
+
+    /// Close the connection with a code and reason
+    pub async fn close<T: Into<String>>(
+        mut self,
+        code: u16,
+        reason: T,
+    ) -> std::result::Result<(), Error> {
+        self.stream
+            .close(Some(tungstenite::protocol::CloseFrame {
+                code: CloseCode::from(code),
+                reason: reason.into().into(),
+            }))
+            .await
+    }
+
+    /// Subprotocol negotiated during the handshake
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use std::path::PathBuf;
+
+use tela::{prelude::*, Server};
+
+#[catch(404)]
+fn not_found(code: u16, reason: String) -> String {
+    format!("caught {} {}", code, reason)
+}
+
+/// Directory with a single file that the asset mounts serve
+fn root() -> String {
+    let root: PathBuf = std::env::temp_dir().join("tela-assets-test");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("app.js"), "console.log(1)").unwrap();
+    root.to_string_lossy().to_string()
+}
+
+#[test]
+fn existing_asset_is_served() {
+    common::serve(47811, true, || Server::new().assets(root()));
+    let (status, body) = common::request(47811, "GET /app.js HTTP/1.1\r\nHost: localhost", b"");
+    assert_eq!(status, 200);
+    assert_eq!(body, "console.log(1)");
+}
+
+#[test]
+fn missing_asset_is_caught() {
+    common::serve(47812, true, || {
+        Server::new().assets(root()).catch(not_found)
+    });
+    let (status, body) = common::request(47812, "GET /missing.js HTTP/1.1\r\nHost: localhost", b"");
+    assert_eq!(status, 404);
+    assert_eq!(body, "caught 404 File not found");
+}
+
+#[test]
+fn missing_asset_is_a_problem() {
+    common::serve(47813, true, || {
+        Server::new().assets(root()).problem_details(true)
+    });
+    let (status, body) = common::request(47813, "GET /missing.js HTTP/1.1\r\nHost: localhost", b"");
+    assert_eq!(status, 404);
+    assert!(body.contains("\"detail\":\"File not found\""), "{}", body);
+    assert!(body.contains("\"instance\":\"/missing.js\""), "{}", body);
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use tela::{prelude::*, request::BodyStream, Server};
+
+#[post("/upload")]
+fn upload(mut body: BodyStream) -> Result<String> {
+    let mut size = 0;
+    while let Some(chunk) = body.chunk() {
+        size += chunk?.len();
+    }
+    Ok(size.to_string())
+}
+
+fn server() -> Server {
+    Server::new().route(upload)
+}
+
+/// Larger than a single socket read so the endpoint must wait on the connection for the rest
+const SIZE: usize = 256 * 1024;
+
+#[test]
+fn streams_large_body_multi_thread() {
+    common::serve(47801, true, server);
+    let (status, body) = common::request(
+        47801,
+        "POST /upload HTTP/1.1\r\nHost: localhost",
+        &[b'a'; SIZE],
+    );
+    assert_eq!(status, 200);
+    assert_eq!(body, SIZE.to_string());
+}
+
+#[test]
+fn streams_large_body_current_thread() {
+    common::serve(47802, false, server);
+    let (status, body) = common::request(
+        47802,
+        "POST /upload HTTP/1.1\r\nHost: localhost",
+        &[b'a'; SIZE],
+    );
+    assert_eq!(status, 200);
+    assert_eq!(body, SIZE.to_string());
+}
+
+#[test]
+fn encoded_stream_is_unsupported() {
+    common::serve(47806, true, server);
+    let (status, _) = common::request(
+        47806,
+        "POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Encoding: gzip",
+        b"tela",
+    );
+    assert_eq!(status, 415);
+}
@@ -0,0 +1,57 @@
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    thread,
+    time::{Duration, Instant},
+};
+
+use tela::{bump::tokio::runtime::Builder, Server};
+
+/// Serve on a background thread with either a `multi_thread` or `current_thread` runtime
+pub fn serve(port: u16, multi_thread: bool, server: fn() -> Server) {
+    thread::spawn(move || {
+        let runtime = match multi_thread {
+            true => Builder::new_multi_thread().enable_all().build(),
+            false => Builder::new_current_thread().enable_all().build(),
+        }
+        .unwrap();
+        runtime.block_on(async move { server().serve(([127, 0, 0, 1], port)).await })
+    });
+}
+
+/// Send a raw request and return the status code and body of the response
+///
+/// The response must arrive within a few seconds so a stalled connection fails the test.
+pub fn request(port: u16, head: &str, body: &[u8]) -> (u16, String) {
+    let start = Instant::now();
+    let mut stream = loop {
+        match TcpStream::connect(("127.0.0.1", port)) {
+            Ok(stream) => break stream,
+            Err(_) if start.elapsed() < Duration::from_secs(5) => {
+                thread::sleep(Duration::from_millis(25))
+            }
+            Err(err) => panic!("Failed to connect to test server: {}", err),
+        }
+    };
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    write!(
+        stream,
+        "{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        head,
+        body.len()
+    )
+    .unwrap();
+    stream.write_all(body).unwrap();
+
+    let mut response = Vec::new();
+    stream
+        .read_to_end(&mut response)
+        .expect("No response from test server");
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    (status, body.to_string())
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use tela::{
+    prelude::*,
+    request::{FromMultipart, Multipart, MultipartForm, TempFile},
+    Server,
+};
+
+#[derive(FromMultipart)]
+struct Profile {
+    name: String,
+    tags: Vec<String>,
+    avatar: TempFile,
+}
+
+#[post("/profile")]
+fn profile(MultipartForm(form): MultipartForm<Profile>) -> String {
+    format!(
+        "{} {} {}",
+        form.name,
+        form.tags.join(","),
+        form.avatar.size()
+    )
+}
+
+#[post("/fields")]
+fn fields(mut multipart: Multipart) -> Result<String> {
+    let mut names = Vec::new();
+    while let Some(field) = multipart.next_field()? {
+        let name = field.name().unwrap_or("").to_string();
+        names.push(format!("{}={}", name, field.bytes()?.len()));
+    }
+    Ok(names.join("&"))
+}
+
+fn server() -> Server {
+    Server::new().route(profile).route(fields)
+}
+
+const BOUNDARY: &str = "tela-boundary";
+
+fn form(file: usize) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (name, value) in [("name", "tela"), ("tags", "a"), ("tags", "b")] {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                BOUNDARY, name, value
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"avatar\"; filename=\"avatar.bin\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+            BOUNDARY
+        )
+        .as_bytes(),
+    );
+    body.extend(vec![b'a'; file]);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+    body
+}
+
+fn head(path: &str) -> String {
+    format!(
+        "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: multipart/form-data; boundary={}",
+        path, BOUNDARY
+    )
+}
+
+#[test]
+fn derive_small_and_large_uploads() {
+    common::serve(47803, true, server);
+
+    let (status, body) = common::request(47803, &head("/profile"), &form(16));
+    assert_eq!(status, 200);
+    assert_eq!(body, "tela a,b 16");
+
+    let (status, body) = common::request(47803, &head("/profile"), &form(5 * 1024 * 1024));
+    assert_eq!(status, 200);
+    assert_eq!(body, format!("tela a,b {}", 5 * 1024 * 1024));
+}
+
+#[test]
+fn multipart_fields() {
+    common::serve(47804, false, server);
+
+    let (status, body) = common::request(47804, &head("/fields"), &form(64 * 1024));
+    assert_eq!(status, 200);
+    assert_eq!(body, format!("name=4&tags=1&tags=1&avatar={}", 64 * 1024));
+}
+
+#[test]
+fn missing_field() {
+    common::serve(47805, true, server);
+
+    let body = format!("--{}--\r\n", BOUNDARY);
+    let (status, _) = common::request(47805, &head("/profile"), body.as_bytes());
+    assert_eq!(status, 422);
+}